@@ -14,6 +14,11 @@ input! {
         pub struct SignalState<SignalId> {
             quit,
         }
+
+        [axis]
+        pub struct AxisState<AxisId> {
+            throttle,
+        }
     }
 }
 
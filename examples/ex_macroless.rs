@@ -13,6 +13,70 @@ enum SignalId {
     Quit,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AxisId {
+    Throttle,
+}
+
+impl NamedIndex for ButtonId {
+    fn all() -> &'static [ButtonId] {
+        &[ButtonId::Jump, ButtonId::Shoot]
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            ButtonId::Jump => "jump",
+            ButtonId::Shoot => "shoot",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<ButtonId> {
+        match name {
+            "jump" => Some(ButtonId::Jump),
+            "shoot" => Some(ButtonId::Shoot),
+            _ => None,
+        }
+    }
+}
+
+impl NamedIndex for SignalId {
+    fn all() -> &'static [SignalId] {
+        &[SignalId::Quit]
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            SignalId::Quit => "quit",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<SignalId> {
+        match name {
+            "quit" => Some(SignalId::Quit),
+            _ => None,
+        }
+    }
+}
+
+impl NamedIndex for AxisId {
+    fn all() -> &'static [AxisId] {
+        &[AxisId::Throttle]
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            AxisId::Throttle => "throttle",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<AxisId> {
+        match name {
+            "throttle" => Some(AxisId::Throttle),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 struct ButtonState {
     pub jump: ButtonValue,
@@ -24,10 +88,16 @@ struct SignalState {
     pub quit: bool,
 }
 
+#[derive(Debug, Clone, Default)]
+struct AxisState {
+    pub throttle: AxisValue,
+}
+
 #[derive(Debug, Clone, Default)]
 struct Input {
     pub button: ButtonState,
     pub signal: SignalState,
+    pub axis: AxisState,
 }
 
 impl AdvanceFrame for Input {
@@ -35,17 +105,20 @@ impl AdvanceFrame for Input {
         self.button.jump.advance_frame();
         self.button.shoot.advance_frame();
         self.signal.quit.advance_frame();
+        self.axis.throttle.advance_frame();
     }
 }
 
 impl InputState for Input {
     type ButtonId = ButtonId;
     type SignalId = SignalId;
+    type AxisId = AxisId;
+    type VectorId = NoInputs;
 
     fn get_button<'a>(&'a mut self, id: &Self::ButtonId) -> &'a mut ButtonValue {
-        match id {
-            &ButtonId::Jump => &mut self.button.jump,
-            &ButtonId::Shoot => &mut self.button.shoot,
+        match *id {
+            ButtonId::Jump => &mut self.button.jump,
+            ButtonId::Shoot => &mut self.button.shoot,
         }
     }
 
@@ -54,6 +127,24 @@ impl InputState for Input {
             &SignalId::Quit => &mut self.signal.quit,
         }
     }
+
+    fn get_axis<'a>(&'a mut self, id: &Self::AxisId) -> &'a mut AxisValue {
+        match id {
+            &AxisId::Throttle => &mut self.axis.throttle,
+        }
+    }
+
+    fn get_vector<'a>(&'a mut self, id: &Self::VectorId) -> &'a mut VectorValue {
+        match *id {}
+    }
+
+    fn get_wheel(&mut self) -> Option<&mut WheelValue> {
+        None
+    }
+
+    fn get_text(&mut self) -> Option<&mut TextValue> {
+        None
+    }
 }
 
 fn main() {
@@ -83,4 +174,13 @@ fn main() {
     let change = InputChange::Signal(Signal::QuitRequest);
     map.apply(&change, &mut input);
     println!("2 Quit: {}", input.signal.quit);
+
+    input.get_axis(&AxisId::Throttle).value = 0.5;
+    println!("2 Throttle: {:?}", input.axis.throttle);
+    input.advance_frame();
+    println!("3 Throttle: {:?}", input.axis.throttle);
+
+    for id in ButtonId::all() {
+        println!("Button {}: {:?}", id.name(), map.button_sources(*id));
+    }
 }
@@ -7,8 +7,11 @@ extern crate rsdl2;
 
 #[cfg(feature = "rsdl2-support")]
 use rsdl2::Rect;
+#[cfg(feature = "rsdl2-support")]
 use dalgi::input::*;
+#[cfg(feature = "rsdl2-support")]
 use std::time::Duration;
+#[cfg(feature = "rsdl2-support")]
 use std::thread;
 
 input! {
@@ -17,14 +20,17 @@ input! {
         pub struct ButtonState<ButtonId> {
             jump,
             shoot,
-            left,
-            right,
         }
         
         [signal]
         pub struct SignalState<SignalId> {
             quit,
         }
+
+        [axis]
+        pub struct AxisState<AxisId> {
+            horizontal,
+        }
     }
 }
 
@@ -48,17 +54,18 @@ fn main() {
     let mut map = InputMap::new();
     map.add_button(ButtonId::shoot, Key::Space);
     map.add_button(ButtonId::jump, Key::Up);
-    map.add_button(ButtonId::right, Key::Right);
-    map.add_button(ButtonId::left, Key::Left);
+    map.add_virtual_axis(AxisId::horizontal, Key::Left, Key::Right, BothHeld::Cancel);
     map.add_signal(SignalId::quit, Signal::QuitRequest);
+    map.add_button(ButtonId::shoot, GamepadButton::RightTrigger);
 
+    let mut sdl_events = Rsdl2Events::new();
     let mut input = Input::new();
 
     'main: loop {
         input.advance_frame();
 
         for event in event_context.events() {
-            map.apply(&event, &mut input);
+            map.apply(&sdl_events.changes(&event), &mut input);
         }
 
         if input.signal.quit {
@@ -75,16 +82,7 @@ fn main() {
         if input.button.shoot.pressed {
             println!("Shoot!");
         }
-        match (input.button.left.held, input.button.right.held) {
-            (true, true) => {} //println!("Holding still!"),
-            (true, false) => {
-                rect.move_by(-10, 0);
-            }
-            (false, true) => {
-                rect.move_by(10, 0);
-            }
-            _ => {}
-        }
+        rect.move_by((input.axis.horizontal.value * 10.0) as i32, 0);
 
         renderer.color(clear_color).clear().unwrap();
         renderer.color(cornflower).fill_rect(rect).unwrap();
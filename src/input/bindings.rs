@@ -0,0 +1,485 @@
+//! Reading and writing input bindings as text.
+//!
+//! # Format
+//! ```text
+//! # Comments start with '#'.
+//! [button]
+//! jump = space, [w], pad:south
+//! shoot = mouse:left  # Trailing comments are fine too.
+//!
+//! [signal]
+//! quit = signal:quit_request, escape
+//!
+//! [axis]
+//! throttle = pad:right_trigger_axis
+//! ```
+//!
+//! Each line binds an action to a comma-separated list of inputs, in the
+//! format of `InputDesc::parse`. Bindings before the first section are
+//! button bindings.
+//!
+//! Virtual axes, vectors and sequences are not part of the format.
+
+use std::error::Error;
+use std::fmt;
+use super::description::InputDesc;
+use super::map::{InputMap, ButtonUpdateSource, SignalUpdateSource, AxisUpdateSource};
+use super::state::{InputIndex, NamedIndex};
+
+/// A section of a bindings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Button,
+    Signal,
+    Axis,
+}
+
+impl Section {
+    fn name(&self) -> &'static str {
+        match *self {
+            Section::Button => "button",
+            Section::Signal => "signal",
+            Section::Axis => "axis",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Section> {
+        Some(match name {
+            "button" => Section::Button,
+            "signal" => Section::Signal,
+            "axis" => Section::Axis,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether the input can drive actions of this section.
+    fn accepts(&self, desc: &InputDesc) -> bool {
+        match *self {
+            Section::Button => ButtonUpdateSource::try_from(desc.clone()).is_some(),
+            Section::Signal => SignalUpdateSource::try_from(desc.clone()).is_some(),
+            Section::Axis => AxisUpdateSource::try_from(desc.clone()).is_some(),
+        }
+    }
+}
+
+/// A line of a bindings file.
+#[derive(Debug, Clone)]
+enum Line {
+    /// A blank line or a comment, which is kept as it is.
+    Verbatim(String),
+    /// A section header.
+    Section {
+        section: Section,
+        indent: String,
+        /// The trailing comment, including the whitespace before it.
+        comment: String,
+    },
+    /// A binding of an action to a list of inputs.
+    Binding {
+        section: Section,
+        /// The line number and column of the action name.
+        position: (usize, usize),
+        indent: String,
+        action: String,
+        inputs: Vec<InputDesc>,
+        /// The trailing comment, including the whitespace before it.
+        comment: String,
+    },
+}
+
+/// What went wrong when reading a bindings file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingsErrorKind {
+    /// The line is neither a section, a binding nor a comment.
+    MissingEquals,
+    /// The section name is not `button`, `signal` or `axis`.
+    UnknownSection(String),
+    /// No action of the section has this name.
+    UnknownAction(String),
+    /// The input could not be parsed.
+    UnknownInput(String),
+    /// The input can't drive actions of this section
+    /// (ie: a gamepad axis in the `[button]` section).
+    InvalidInput(String),
+}
+
+/// An error in a bindings file, with the position where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingsError {
+    /// The line of the error (starting from 1).
+    pub line: usize,
+    /// The column of the error (starting from 1).
+    pub column: usize,
+    /// What went wrong.
+    pub kind: BindingsErrorKind,
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::BindingsErrorKind::*;
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match self.kind {
+            MissingEquals => write!(f, "expected 'action = inputs'"),
+            UnknownSection(ref name) => write!(f, "unknown section '{}'", name),
+            UnknownAction(ref name) => write!(f, "unknown action '{}'", name),
+            UnknownInput(ref name) => write!(f, "unknown input '{}'", name),
+            InvalidInput(ref name) => write!(f, "input '{}' can't be used in this section", name),
+        }
+    }
+}
+
+impl Error for BindingsError {
+    fn description(&self) -> &str {
+        "invalid input bindings"
+    }
+}
+
+/// Returns the 1-based column of the given byte offset in the line.
+fn column(line: &str, offset: usize) -> usize {
+    line[..offset].chars().count() + 1
+}
+
+/// A bindings file, which keeps its comments and layout when it is updated.
+#[derive(Debug, Clone, Default)]
+pub struct Bindings {
+    lines: Vec<Line>,
+}
+
+impl Bindings {
+    /// Creates an empty bindings file.
+    pub fn new() -> Bindings {
+        Bindings { lines: Vec::new() }
+    }
+
+    /// Creates a bindings file with the bindings of the given map.
+    pub fn from_map<BI, NI, AI, VI>(map: &InputMap<BI, NI, AI, VI>) -> Bindings
+        where BI: NamedIndex,
+              NI: NamedIndex,
+              AI: NamedIndex,
+              VI: InputIndex
+    {
+        let mut bindings = Bindings::new();
+        bindings.update_from(map);
+        bindings
+    }
+
+    /// Parses a bindings file.
+    ///
+    /// Action names aren't checked until the bindings are read into a map.
+    pub fn parse(text: &str) -> Result<Bindings, BindingsError> {
+        let mut lines = Vec::new();
+        let mut section = Section::Button;
+        for (index, raw) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |offset: usize, kind| {
+                Err(BindingsError {
+                    line: line_number,
+                    column: column(raw, offset),
+                    kind,
+                })
+            };
+            let content_end = raw.find('#').unwrap_or(raw.len());
+            let content = raw[..content_end].trim_end();
+            let comment = &raw[content.len()..];
+            let trimmed = content.trim_start();
+            let indent = &content[..content.len() - trimmed.len()];
+
+            if trimmed.is_empty() {
+                lines.push(Line::Verbatim(raw.to_string()));
+            } else if let Some(equals) = content.find('=') {
+                let action = content[..equals].trim();
+                let mut inputs = Vec::new();
+                let mut offset = equals + 1;
+                for part in content[equals + 1..].split(',') {
+                    let name = part.trim();
+                    let start = offset + (part.len() - part.trim_start().len());
+                    offset += part.len() + 1;
+                    if name.is_empty() {
+                        continue;
+                    }
+                    let desc = match InputDesc::parse(name) {
+                        Some(desc) => desc,
+                        None => return error(start, BindingsErrorKind::UnknownInput(name.to_string())),
+                    };
+                    if !section.accepts(&desc) {
+                        return error(start, BindingsErrorKind::InvalidInput(name.to_string()));
+                    }
+                    inputs.push(desc);
+                }
+                lines.push(Line::Binding {
+                    section,
+                    position: (line_number, column(raw, indent.len())),
+                    indent: indent.to_string(),
+                    action: action.to_string(),
+                    inputs,
+                    comment: comment.to_string(),
+                });
+            } else if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                let name = trimmed[1..trimmed.len() - 1].trim();
+                section = match Section::from_name(name) {
+                    Some(section) => section,
+                    None => {
+                        let offset = indent.len() + trimmed.find(name).unwrap_or(0);
+                        return error(offset, BindingsErrorKind::UnknownSection(name.to_string()));
+                    }
+                };
+                lines.push(Line::Section {
+                    section,
+                    indent: indent.to_string(),
+                    comment: comment.to_string(),
+                });
+            } else {
+                return error(indent.len(), BindingsErrorKind::MissingEquals);
+            }
+        }
+        Ok(Bindings { lines })
+    }
+
+    /// Adds the bindings of this file to the given map.
+    ///
+    /// If any action is unknown, nothing is added. To fall back to default
+    /// bindings for actions missing from the file, read the file into an
+    /// empty map and use `InputMap::add_unbound_buttons_from`.
+    pub fn read_into<BI, NI, AI, VI>(&self, map: &mut InputMap<BI, NI, AI, VI>) -> Result<(), BindingsError>
+        where BI: NamedIndex,
+              NI: NamedIndex,
+              AI: NamedIndex,
+              VI: InputIndex
+    {
+        // Check every action before changing the map.
+        for line in &self.lines {
+            if let Line::Binding { section, position: (line, column), ref action, .. } = *line {
+                let known = match section {
+                    Section::Button => BI::from_name(action).is_some(),
+                    Section::Signal => NI::from_name(action).is_some(),
+                    Section::Axis => AI::from_name(action).is_some(),
+                };
+                if !known {
+                    return Err(BindingsError {
+                        line,
+                        column,
+                        kind: BindingsErrorKind::UnknownAction(action.clone()),
+                    });
+                }
+            }
+        }
+        for line in &self.lines {
+            if let Line::Binding { section, ref action, ref inputs, .. } = *line {
+                for desc in inputs {
+                    match section {
+                        Section::Button => {
+                            let source = ButtonUpdateSource::try_from(desc.clone()).unwrap();
+                            map.add_button(BI::from_name(action).unwrap(), source);
+                        }
+                        Section::Signal => {
+                            let source = SignalUpdateSource::try_from(desc.clone()).unwrap();
+                            map.add_signal(NI::from_name(action).unwrap(), source);
+                        }
+                        Section::Axis => {
+                            let source = AxisUpdateSource::try_from(desc.clone()).unwrap();
+                            map.add_axis(AI::from_name(action).unwrap(), source);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the bindings in this file with the bindings of the given map.
+    ///
+    /// Comments, blank lines and the order of the existing bindings are kept.
+    /// Actions that are bound by the map but not by the file are added at the
+    /// end of their section, and actions that are no longer bound are kept
+    /// without any inputs.
+    pub fn update_from<BI, NI, AI, VI>(&mut self, map: &InputMap<BI, NI, AI, VI>)
+        where BI: NamedIndex,
+              NI: NamedIndex,
+              AI: NamedIndex,
+              VI: InputIndex
+    {
+        let sources = |section: Section, action: &str| -> Option<Vec<InputDesc>> {
+            match section {
+                Section::Button => {
+                    BI::from_name(action)
+                        .map(|id| map.button_sources(id).into_iter().map(|s| s.into()).collect())
+                }
+                Section::Signal => {
+                    NI::from_name(action)
+                        .map(|id| map.signal_sources(id).into_iter().map(|s| s.into()).collect())
+                }
+                Section::Axis => {
+                    AI::from_name(action)
+                        .map(|id| map.axis_sources(id).into_iter().map(|s| s.into()).collect())
+                }
+            }
+        };
+
+        // Update the existing lines.
+        let mut written: Vec<(Section, String)> = Vec::new();
+        for line in &mut self.lines {
+            if let Line::Binding { section, ref action, ref mut inputs, .. } = *line {
+                let mut bound = match sources(section, action) {
+                    Some(bound) => bound,
+                    None => continue,
+                };
+                if written.iter().any(|&(s, ref a)| s == section && a == action) {
+                    // The inputs were written to an earlier line.
+                    inputs.clear();
+                    continue;
+                }
+                inputs.retain(|desc| bound.contains(desc));
+                bound.retain(|desc| !inputs.contains(desc));
+                bound.sort_by_key(|desc| desc.to_string());
+                inputs.extend(bound);
+                written.push((section, action.clone()));
+            }
+        }
+
+        // Add the actions that weren't in the file.
+        let mut missing: Vec<(Section, String)> = Vec::new();
+        missing.extend(map.bound_buttons().iter().map(|id| (Section::Button, id.name().to_string())));
+        missing.extend(map.bound_signals().iter().map(|id| (Section::Signal, id.name().to_string())));
+        missing.extend(map.bound_axes().iter().map(|id| (Section::Axis, id.name().to_string())));
+        missing.retain(|&(section, ref action)| {
+            !written.iter().any(|&(s, ref a)| s == section && a == action)
+        });
+        missing.sort_by_key(|&(section, ref action)| (section as u8, action.clone()));
+        for (section, action) in missing {
+            let mut inputs = sources(section, &action).unwrap_or_else(Vec::new);
+            if inputs.is_empty() {
+                // Only bound through virtual axes and the like.
+                continue;
+            }
+            inputs.sort_by_key(|desc| desc.to_string());
+            let line = Line::Binding {
+                section,
+                position: (0, 0),
+                indent: String::new(),
+                action,
+                inputs,
+                comment: String::new(),
+            };
+            self.insert_in_section(section, line);
+        }
+    }
+
+    /// Inserts a line after the last binding of the given section, adding the
+    /// section at the end if it doesn't exist.
+    fn insert_in_section(&mut self, section: Section, line: Line) {
+        let mut current = Section::Button;
+        let mut last_in_section = None;
+        for (index, existing) in self.lines.iter().enumerate() {
+            match *existing {
+                Line::Section { section: s, .. } => {
+                    current = s;
+                    if s == section {
+                        last_in_section = Some(index);
+                    }
+                }
+                Line::Binding { .. } if current == section => last_in_section = Some(index),
+                _ => {}
+            }
+        }
+        match last_in_section {
+            Some(index) => self.lines.insert(index + 1, line),
+            None => {
+                if !self.lines.is_empty() {
+                    self.lines.push(Line::Verbatim(String::new()));
+                }
+                self.lines.push(Line::Section {
+                    section,
+                    indent: String::new(),
+                    comment: String::new(),
+                });
+                self.lines.push(line);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Bindings {
+    /// Writes the bindings file, with its comments.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            match *line {
+                Line::Verbatim(ref text) => writeln!(f, "{}", text)?,
+                Line::Section { section, ref indent, ref comment } => {
+                    writeln!(f, "{}[{}]{}", indent, section.name(), comment)?
+                }
+                Line::Binding { ref indent, ref action, ref inputs, ref comment, .. } => {
+                    write!(f, "{}{} =", indent, action)?;
+                    for (i, desc) in inputs.iter().enumerate() {
+                        let separator = if i == 0 { " " } else { ", " };
+                        write!(f, "{}{}", separator, desc)?;
+                    }
+                    writeln!(f, "{}", comment)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bindings, BindingsError, BindingsErrorKind};
+
+    const FILE: &str = "\
+# Comments start with '#'.
+jump = space, [w], pad:south
+[button]  # The header comment stays here.
+  shoot = mouse:left  # Trailing comments are fine too.
+crouch = other(1073742106), q & [e]
+
+[signal]
+quit = signal:quit_request, escape
+[axis]
+throttle = pad1:right_trigger_axis
+";
+
+    fn error(text: &str) -> BindingsError {
+        Bindings::parse(text).unwrap_err()
+    }
+
+    #[test]
+    fn writing_keeps_the_file() {
+        let bindings = Bindings::parse(FILE).unwrap();
+        assert_eq!(bindings.to_string(), FILE);
+    }
+
+    #[test]
+    fn written_files_parse_again() {
+        let written = Bindings::parse(FILE).unwrap().to_string();
+        let reparsed = Bindings::parse(&written).unwrap();
+        assert_eq!(reparsed.to_string(), written);
+    }
+
+    #[test]
+    fn errors_have_positions() {
+        assert_eq!(error("jump"),
+                   BindingsError {
+                       line: 1,
+                       column: 1,
+                       kind: BindingsErrorKind::MissingEquals,
+                   });
+        assert_eq!(error("[button]\n[nope]"),
+                   BindingsError {
+                       line: 2,
+                       column: 2,
+                       kind: BindingsErrorKind::UnknownSection("nope".to_string()),
+                   });
+        assert_eq!(error("jump = space, nope"),
+                   BindingsError {
+                       line: 1,
+                       column: 15,
+                       kind: BindingsErrorKind::UnknownInput("nope".to_string()),
+                   });
+        assert_eq!(error("[axis]\nthrottle = space"),
+                   BindingsError {
+                       line: 2,
+                       column: 12,
+                       kind: BindingsErrorKind::InvalidInput("space".to_string()),
+                   });
+    }
+}
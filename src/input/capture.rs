@@ -0,0 +1,161 @@
+//! Functionality to capture the next input as a new binding.
+
+use super::key::Key;
+use super::description::{Keytype, Mods, KeyDesc};
+use super::change::{ButtonChange, InputChange};
+use super::map::{ButtonUpdateSource, SignalUpdateSource};
+
+/// Which kind of binding is captured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureKind {
+    Button,
+    Signal,
+}
+
+/// A device that input can be captured from (see `Capture::devices`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureDevice {
+    /// The keyboard.
+    Keyboard,
+    /// Mouse buttons and the mouse wheel.
+    Mouse,
+    /// Gamepad buttons.
+    Gamepad,
+}
+
+impl CaptureDevice {
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Describes which input should be captured as a new binding
+/// (see `InputMap::capture`).
+///
+/// By default, input from every device is captured, keys are captured as
+/// key codes, bare modifier keys are ignored (so that `ctrl+s` can be
+/// captured) and escape cancels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capture {
+    kind: CaptureKind,
+    /// The bits of the accepted devices.
+    devices: u8,
+    keytype: Keytype,
+    allow_modifiers: bool,
+    cancel: Option<Key>,
+}
+
+/// The result of a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Captured {
+    /// An input for a button action.
+    Button(ButtonUpdateSource),
+    /// An input for a signal action.
+    Signal(SignalUpdateSource),
+    /// The cancel key was pressed.
+    Cancelled,
+}
+
+impl Capture {
+    /// Captures an input for a button action.
+    pub fn button() -> Capture {
+        Capture {
+            kind: CaptureKind::Button,
+            devices: !0,
+            keytype: Keytype::Keycode,
+            allow_modifiers: false,
+            cancel: Some(Key::Escape),
+        }
+    }
+
+    /// Captures an input for a signal action.
+    pub fn signal() -> Capture {
+        Capture { kind: CaptureKind::Signal, ..Capture::button() }
+    }
+
+    /// Only captures input from the given devices (ie: for the gamepad page
+    /// of a settings screen). The cancel key works regardless.
+    pub fn devices(mut self, devices: &[CaptureDevice]) -> Capture {
+        self.devices = devices.iter().fold(0, |bits, &device| bits | device.bit());
+        self
+    }
+
+    /// Captures keys as the given key type.
+    ///
+    /// Key changes of the other type are ignored, since some backends report
+    /// every key both as a key code and as a scan code.
+    pub fn keytype(mut self, keytype: Keytype) -> Capture {
+        self.keytype = keytype;
+        self
+    }
+
+    /// Captures modifier keys by themselves (ie: to bind `left_shift`),
+    /// rather than waiting for a key to be pressed with them.
+    pub fn allow_modifiers(mut self) -> Capture {
+        self.allow_modifiers = true;
+        self
+    }
+
+    /// Sets the key that cancels the capture, or `None` to only stop the
+    /// capture through `InputMap::stop_capture`.
+    pub fn cancel_key(mut self, key: Option<Key>) -> Capture {
+        self.cancel = key;
+        self
+    }
+
+    /// Returns the result of the capture if the change completes it.
+    ///
+    /// Signals are never captured, so that a quit request isn't mistaken for
+    /// a binding.
+    pub fn check(&self, change: &InputChange) -> Option<Captured> {
+        let (device, captured): (CaptureDevice, ButtonUpdateSource) = match *change {
+            InputChange::Key(desc, ButtonChange::Pressed) => {
+                if Some(desc.key) == self.cancel {
+                    return Some(Captured::Cancelled);
+                }
+                if desc.keytype != self.keytype {
+                    return None;
+                }
+                let own = Mods::of_key(desc.key);
+                if !own.is_empty() && !self.allow_modifiers {
+                    return None;
+                }
+                let mut key = KeyDesc::new(desc.key).mods((desc.mods - own).either_side());
+                key.keytype = desc.keytype;
+                (CaptureDevice::Keyboard, key.into())
+            }
+            InputChange::MouseButton(button, ButtonChange::Pressed) => {
+                (CaptureDevice::Mouse, button.into())
+            }
+            InputChange::Wheel(direction, notches) if notches > 0.0 => {
+                (CaptureDevice::Mouse, direction.into())
+            }
+            InputChange::GamepadButton(index, button, ButtonChange::Pressed) => {
+                (CaptureDevice::Gamepad, ButtonUpdateSource::GamepadButton(index, button))
+            }
+            _ => return None,
+        };
+        if self.devices & device.bit() == 0 {
+            return None;
+        }
+        Some(match self.kind {
+            CaptureKind::Button => Captured::Button(captured),
+            CaptureKind::Signal => {
+                let desc = captured.into();
+                Captured::Signal(SignalUpdateSource::try_from(desc).unwrap())
+            }
+        })
+    }
+
+    /// Returns whether the change still reaches the bindings of the input map
+    /// during the capture: releases and gamepad axes, so that held actions
+    /// don't get stuck, and signals, so that a quit request is never lost.
+    pub fn passes_through(&self, change: &InputChange) -> bool {
+        matches!(*change,
+                 InputChange::Key(_, ButtonChange::Released) |
+                 InputChange::MouseButton(_, ButtonChange::Released) |
+                 InputChange::GamepadButton(_, _, ButtonChange::Released) |
+                 InputChange::GamepadAxis(..) |
+                 InputChange::Signal(_))
+    }
+}
@@ -1,6 +1,10 @@
 //! Functionality to describe changes to an input state.
 use super::description::{KeyDesc, InputDesc};
+use super::mouse::{MouseButton, WheelDirection};
+use super::gamepad::{GamepadButton, GamepadAxis};
 use super::signal::Signal;
+use super::text::TextChange;
+use super::state::same_bits;
 
 /// The state of a button.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -14,12 +18,27 @@ pub enum ButtonChange {
 }
 
 /// The representation of a change to the input state.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Analog values are compared bit for bit, so that changes can be `Eq`.
+#[derive(Debug, Clone)]
 pub enum InputChange {
     /// A keyboard button was changed.
     Key(KeyDesc, ButtonChange),
     /// A signal was sent.
     Signal(Signal),
+    /// A mouse button was changed.
+    MouseButton(MouseButton, ButtonChange),
+    /// The mouse wheel was scrolled a number of notches in a direction.
+    ///
+    /// The number is positive, and can be fractional with high-resolution
+    /// wheels and touchpads.
+    Wheel(WheelDirection, f32),
+    /// A button on the gamepad with the given index was changed.
+    GamepadButton(u8, GamepadButton, ButtonChange),
+    /// An axis on the gamepad with the given index moved to a new value.
+    GamepadAxis(u8, GamepadAxis, f32),
+    /// Text was entered or composed.
+    Text(TextChange),
 }
 
 impl InputChange {
@@ -31,16 +50,77 @@ impl InputChange {
         match *self {
             Key(desc, _) => InputDesc::Key(desc),
             Signal(signal) => InputDesc::Signal(signal),
+            MouseButton(button, _) => InputDesc::MouseButton(button),
+            Wheel(direction, _) => InputDesc::Wheel(direction),
+            GamepadButton(index, button, _) => InputDesc::GamepadButton(index, button),
+            GamepadAxis(index, axis, _) => InputDesc::GamepadAxis(index, axis),
+            Text(_) => InputDesc::Text,
+        }
+    }
+
+    /// Returns how the button changed, if this change pertains to a button.
+    pub fn button_change(&self) -> Option<ButtonChange> {
+        use self::InputChange::*;
+        match *self {
+            Key(_, change) | MouseButton(_, change) | GamepadButton(_, _, change) => Some(change),
+            Signal(_) | Wheel(..) | GamepadAxis(..) | Text(_) => None,
+        }
+    }
+
+    /// Returns how this change affects a button bound to the input `bound`,
+    /// if at all.
+    ///
+    /// Releasing a key affects every binding to that key, regardless of
+    /// modifiers, so that letting go of `ctrl` before `s` still releases a
+    /// `ctrl+s` binding.
+    pub fn button_change_for(&self, bound: &InputDesc) -> Option<ButtonChange> {
+        if bound.matches(&self.input()) {
+            return self.button_change();
+        }
+        match (bound, self) {
+            (InputDesc::Key(bound), &InputChange::Key(changed, ButtonChange::Released))
+                if bound.key == changed.key && bound.keytype == changed.keytype => {
+                Some(ButtonChange::Released)
+            }
+            _ => None,
         }
     }
 }
 
+impl PartialEq for InputChange {
+    fn eq(&self, other: &InputChange) -> bool {
+        use self::InputChange::*;
+        match (self, other) {
+            (&Key(a, change_a), &Key(b, change_b)) => a == b && change_a == change_b,
+            (&Signal(a), &Signal(b)) => a == b,
+            (&MouseButton(a, change_a), &MouseButton(b, change_b)) => a == b && change_a == change_b,
+            (&Wheel(a, notches_a), &Wheel(b, notches_b)) => a == b && same_bits(notches_a, notches_b),
+            (&GamepadButton(index_a, a, change_a), &GamepadButton(index_b, b, change_b)) => {
+                index_a == index_b && a == b && change_a == change_b
+            }
+            (&GamepadAxis(index_a, a, value_a), &GamepadAxis(index_b, b, value_b)) => {
+                index_a == index_b && a == b && same_bits(value_a, value_b)
+            }
+            (Text(a), Text(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for InputChange {}
+
 impl From<Signal> for InputChange {
     fn from(signal: Signal) -> InputChange {
         InputChange::Signal(signal)
     }
 }
 
+impl From<TextChange> for InputChange {
+    fn from(text: TextChange) -> InputChange {
+        InputChange::Text(text)
+    }
+}
+
 /// A trait to let an 'input event' describe which changes it contains.
 pub trait DescribeInputChanges {
     /// Tells the handler which changes this object represents.
@@ -54,3 +134,17 @@ impl DescribeInputChanges for InputChange {
         handler(self.clone())
     }
 }
+
+/// The changes of a single event, in order (ie: as recorded by
+/// `Recording`).
+impl DescribeInputChanges for Vec<InputChange> {
+    fn describe_changes<F: FnMut(InputChange)>(&self, handler: F) {
+        self.iter().cloned().for_each(handler)
+    }
+}
+
+impl DescribeInputChanges for TextChange {
+    fn describe_changes<F: FnMut(InputChange)>(&self, mut handler: F) {
+        handler(InputChange::Text(self.clone()))
+    }
+}
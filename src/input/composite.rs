@@ -0,0 +1,231 @@
+//! Bindings that combine several input sources into a single action.
+
+use super::change::{ButtonChange, InputChange};
+use super::description::{InputDesc, KeyDesc};
+
+/// What a virtual axis does while both of its buttons are held.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum BothHeld {
+    /// The buttons cancel each other out, and the axis is at rest.
+    Cancel,
+    /// The button that was pressed last decides the direction.
+    LastWins,
+    /// The button that was pressed first decides the direction.
+    FirstWins,
+}
+
+/// An axis driven by a negative and a positive button-type source.
+#[derive(Debug, Clone)]
+pub struct VirtualAxis {
+    negative: InputDesc,
+    positive: InputDesc,
+    policy: BothHeld,
+    negative_held: bool,
+    positive_held: bool,
+    /// Whether the positive button was pressed after the negative one.
+    positive_last: bool,
+}
+
+impl VirtualAxis {
+    /// Creates a new virtual axis at rest.
+    pub fn new(negative: InputDesc, positive: InputDesc, policy: BothHeld) -> VirtualAxis {
+        VirtualAxis {
+            negative,
+            positive,
+            policy,
+            negative_held: false,
+            positive_held: false,
+            positive_last: false,
+        }
+    }
+
+    /// Updates the held buttons from the given change.
+    /// Returns whether the change pertained to this axis.
+    pub fn update(&mut self, change: &InputChange) -> bool {
+        let mut changed = false;
+        if let Some(button_change) = change.button_change_for(&self.negative) {
+            match button_change {
+                ButtonChange::Pressed => {
+                    self.negative_held = true;
+                    self.positive_last = false;
+                }
+                ButtonChange::Released => self.negative_held = false,
+                ButtonChange::Repeated => {}
+            }
+            changed = true;
+        }
+        if let Some(button_change) = change.button_change_for(&self.positive) {
+            match button_change {
+                ButtonChange::Pressed => {
+                    self.positive_held = true;
+                    self.positive_last = true;
+                }
+                ButtonChange::Released => self.positive_held = false,
+                ButtonChange::Repeated => {}
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Returns the current value of the axis (-1, 0 or 1).
+    pub fn value(&self) -> f32 {
+        match (self.negative_held, self.positive_held) {
+            (false, false) => 0.0,
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            (true, true) => {
+                match (self.policy, self.positive_last) {
+                    (BothHeld::Cancel, _) => 0.0,
+                    (BothHeld::LastWins, true) | (BothHeld::FirstWins, false) => 1.0,
+                    (BothHeld::LastWins, false) | (BothHeld::FirstWins, true) => -1.0,
+                }
+            }
+        }
+    }
+}
+
+/// The sources of a vector binding.
+#[derive(Debug, Clone)]
+enum VectorSources {
+    /// Four directional buttons.
+    Buttons {
+        x: VirtualAxis,
+        y: VirtualAxis,
+    },
+    /// Two analog axes (ie: a stick).
+    Axes {
+        x: InputDesc,
+        y: InputDesc,
+        value: (f32, f32),
+    },
+}
+
+/// A two-dimensional value driven by directional buttons or a pair of axes.
+#[derive(Debug, Clone)]
+pub struct VirtualVector {
+    sources: VectorSources,
+    normalize: bool,
+}
+
+impl VirtualVector {
+    /// Creates a vector driven by four directional buttons.
+    pub fn from_buttons(up: InputDesc,
+                        down: InputDesc,
+                        left: InputDesc,
+                        right: InputDesc,
+                        normalize: bool)
+                        -> VirtualVector {
+        VirtualVector {
+            sources: VectorSources::Buttons {
+                x: VirtualAxis::new(left, right, BothHeld::Cancel),
+                y: VirtualAxis::new(up, down, BothHeld::Cancel),
+            },
+            normalize,
+        }
+    }
+
+    /// Creates a vector driven by a horizontal and a vertical axis.
+    pub fn from_axes(x: InputDesc, y: InputDesc, normalize: bool) -> VirtualVector {
+        VirtualVector {
+            sources: VectorSources::Axes {
+                x,
+                y,
+                value: (0.0, 0.0),
+            },
+            normalize,
+        }
+    }
+
+    /// Updates the vector from the given change.
+    /// Returns whether the change pertained to this vector.
+    pub fn update(&mut self, change: &InputChange) -> bool {
+        match self.sources {
+            VectorSources::Buttons { ref mut x, ref mut y } => {
+                // Both must be updated, even if the first one changed.
+                let x_changed = x.update(change);
+                let y_changed = y.update(change);
+                x_changed || y_changed
+            }
+            VectorSources::Axes { ref x, ref y, ref mut value } => {
+                let input = change.input();
+                match *change {
+                    InputChange::GamepadAxis(_, _, new_value) if *x == input => {
+                        value.0 = new_value;
+                        true
+                    }
+                    InputChange::GamepadAxis(_, _, new_value) if *y == input => {
+                        value.1 = new_value;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Returns whether the length of the vector is clamped to 1.
+    pub fn is_normalized(&self) -> bool {
+        self.normalize
+    }
+
+    /// Returns the current value of the vector.
+    ///
+    /// If the vector is normalized, its length is at most 1, so that moving
+    /// diagonally isn't faster than moving straight.
+    pub fn value(&self) -> (f32, f32) {
+        let (x, y) = match self.sources {
+            VectorSources::Buttons { ref x, ref y } => (x.value(), y.value()),
+            VectorSources::Axes { value, .. } => value,
+        };
+        let length = (x * x + y * y).sqrt();
+        if self.normalize && length > 1.0 {
+            (x / length, y / length)
+        } else {
+            (x, y)
+        }
+    }
+}
+
+/// Several keys that trigger a button when held together.
+#[derive(Debug, Clone)]
+pub struct Chord {
+    keys: Vec<InputDesc>,
+    held: Vec<bool>,
+    active: bool,
+}
+
+impl Chord {
+    /// Creates a new chord of the given keys, with none of them held.
+    pub fn new(keys: &[KeyDesc]) -> Chord {
+        Chord {
+            keys: keys.iter().map(|&key| InputDesc::Key(key)).collect(),
+            held: vec![false; keys.len()],
+            active: false,
+        }
+    }
+
+    /// Updates the held keys from the given change.
+    ///
+    /// Returns `Pressed` when the last key of the chord goes down, and
+    /// `Released` when any key goes up while the chord is active.
+    pub fn update(&mut self, change: &InputChange) -> Option<ButtonChange> {
+        for (key, held) in self.keys.iter().zip(self.held.iter_mut()) {
+            match change.button_change_for(key) {
+                Some(ButtonChange::Pressed) => *held = true,
+                Some(ButtonChange::Released) => *held = false,
+                Some(ButtonChange::Repeated) | None => {}
+            }
+        }
+        let all_held = self.held.iter().all(|&held| held);
+        if all_held && !self.active {
+            self.active = true;
+            Some(ButtonChange::Pressed)
+        } else if !all_held && self.active {
+            self.active = false;
+            Some(ButtonChange::Released)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Functionality to layer several input maps.
+
+use std::time::Instant;
+use super::change::{DescribeInputChanges, ButtonChange};
+use super::map::{ActionId, InputMap, MapEvents};
+use super::state::{InputState, InputIndex, NoInputs, AdvanceFrame};
+
+/// Which changes a context keeps from the contexts below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consume {
+    /// Changes that trigger a binding of an enabled action of the context
+    /// (ie: a menu that uses space, but lets movement through).
+    Bound,
+    /// Every change (ie: a modal dialog).
+    All,
+    /// No changes (ie: a HUD that reacts to the same keys as gameplay).
+    Nothing,
+}
+
+/// An input map in a context stack.
+#[derive(Debug, Clone)]
+struct Context<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    name: String,
+    map: InputMap<BI, NI, AI, VI>,
+    consume: Consume,
+}
+
+/// Returns the buttons held by the contexts other than the one at `index`.
+fn held_by_others<BI, NI, AI, VI>(contexts: &[Context<BI, NI, AI, VI>], index: usize) -> Vec<BI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    contexts.iter()
+        .enumerate()
+        .filter(|&(i, _)| i != index)
+        .flat_map(|(_, context)| context.map.held_buttons())
+        .collect()
+}
+
+/// A stack of named input maps (ie: pause menu over HUD over gameplay),
+/// where higher contexts see changes first and can keep them from the
+/// contexts below.
+///
+/// Releases are never consumed, so that a button held in a lower context
+/// doesn't get stuck when a context is pushed on top of it. A button held
+/// through several contexts is pressed when the first source goes down, and
+/// released when the last one goes up, as within a map.
+///
+/// Wheel movement and text are added to the input state once per change,
+/// regardless of how many contexts see it.
+#[derive(Debug, Clone)]
+pub struct ContextStack<BI, NI, AI = NoInputs, VI = NoInputs>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    /// The contexts, from bottom to top.
+    contexts: Vec<Context<BI, NI, AI, VI>>,
+    /// Actions driven by removed contexts, whose state must be updated
+    /// (see `refresh`).
+    pending: Vec<ActionId<BI, NI, AI, VI>>,
+}
+
+impl<BI, NI, AI, VI> ContextStack<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    /// Creates an empty context stack.
+    pub fn new() -> ContextStack<BI, NI, AI, VI> {
+        ContextStack {
+            contexts: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Pushes an enabled context on top of the stack.
+    pub fn push(&mut self, name: &str, map: InputMap<BI, NI, AI, VI>, consume: Consume) {
+        self.contexts.push(Context {
+            name: name.to_string(),
+            map,
+            consume,
+        });
+    }
+
+    /// Removes the top context, and returns its map.
+    ///
+    /// The buttons it held are released, and its axes and vectors are
+    /// reset, on the next refresh (see `refresh`).
+    pub fn pop(&mut self) -> Option<InputMap<BI, NI, AI, VI>> {
+        let mut map = self.contexts.pop().map(|context| context.map);
+        if let Some(ref mut map) = map {
+            self.forget(map);
+        }
+        map
+    }
+
+    /// Removes the named context wherever it is in the stack, and returns its
+    /// map (see `pop`).
+    pub fn remove(&mut self, name: &str) -> Option<InputMap<BI, NI, AI, VI>> {
+        let mut map = self.index_of(name).map(|index| self.contexts.remove(index).map);
+        if let Some(ref mut map) = map {
+            self.forget(map);
+        }
+        map
+    }
+
+    /// Queues the actions driven by a removed map for the next refresh, and
+    /// activates the map again if its context was disabled.
+    fn forget(&mut self, map: &mut InputMap<BI, NI, AI, VI>) {
+        for action in map.driven_actions() {
+            if !self.pending.contains(&action) {
+                self.pending.push(action);
+            }
+        }
+        if !map.is_active() {
+            map.set_active(true);
+        }
+    }
+
+    /// Returns whether the stack has a context with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.index_of(name).is_some()
+    }
+
+    /// Returns the names of the contexts, from bottom to top.
+    pub fn names(&self) -> Vec<&str> {
+        self.contexts.iter().map(|context| &context.name[..]).collect()
+    }
+
+    /// Returns the map of the named context.
+    pub fn get(&self, name: &str) -> Option<&InputMap<BI, NI, AI, VI>> {
+        self.index_of(name).map(|index| &self.contexts[index].map)
+    }
+
+    /// Returns the map of the named context, to change its bindings.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut InputMap<BI, NI, AI, VI>> {
+        match self.index_of(name) {
+            Some(index) => Some(&mut self.contexts[index].map),
+            None => None,
+        }
+    }
+
+    /// Enables or disables the named context. The actions of a disabled
+    /// context are disabled like those of a disabled group
+    /// (see `InputMap::set_group_enabled`): they only see releases, and
+    /// their axes and vectors are kept at rest. A disabled context doesn't
+    /// consume any changes.
+    ///
+    /// Returns whether the context exists.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.index_of(name) {
+            Some(index) => {
+                self.contexts[index].map.set_active(enabled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns whether the named context exists and is enabled.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.index_of(name).is_some_and(|index| self.contexts[index].map.is_active())
+    }
+
+    /// Changes which changes the named context consumes.
+    ///
+    /// Returns whether the context exists.
+    pub fn set_consume(&mut self, name: &str, consume: Consume) -> bool {
+        match self.index_of(name) {
+            Some(index) => {
+                self.contexts[index].consume = consume;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.contexts.iter().rposition(|context| context.name == name)
+    }
+
+    /// Updates the state of actions whose bindings changed since the last
+    /// event, in every context (see `InputMap::refresh`).
+    ///
+    /// Buttons held by removed contexts are released, unless a remaining
+    /// context holds them, and their axes and vectors take the value of the
+    /// remaining bindings, or rest.
+    ///
+    /// This is done at the start of `apply`, so only call this to see the
+    /// changes before the next event.
+    pub fn refresh<S>(&mut self, state: &mut S)
+        where S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
+    {
+        for action in std::mem::take(&mut self.pending) {
+            match action {
+                ActionId::Button(id) => {
+                    let button = state.get_button(&id);
+                    if button.held && !self.contexts.iter().any(|context| context.map.holds(id)) {
+                        let now = self.contexts.last().map_or_else(Instant::now, |context| context.map.now());
+                        button.release(Some(now));
+                    }
+                }
+                ActionId::Signal(_) => {}
+                ActionId::Axis(_) | ActionId::Vector(_) => {
+                    match self.contexts.iter_mut().rev().find(|context| context.map.binds_analog(action)) {
+                        Some(context) => context.map.mark_pending(action),
+                        None => {
+                            match action {
+                                ActionId::Axis(id) => state.get_axis(&id).value = 0.0,
+                                ActionId::Vector(id) => state.get_vector(&id).value = (0.0, 0.0),
+                                ActionId::Button(_) | ActionId::Signal(_) => {}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for index in 0..self.contexts.len() {
+            let held_elsewhere = held_by_others(&self.contexts, index);
+            self.contexts[index].map.refresh_held_elsewhere(state, &held_elsewhere);
+        }
+    }
+
+    /// Applies the changes described by the given event to the input state,
+    /// through the contexts from top to bottom (see `InputMap::apply`).
+    pub fn apply<E, S>(&mut self, event: &E, state: &mut S)
+        where E: DescribeInputChanges,
+              S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
+    {
+        self.refresh(state);
+        for context in &mut self.contexts {
+            context.map.begin_event();
+        }
+        let captures: Vec<_> = self.contexts.iter().map(|context| context.map.capture_in_progress()).collect();
+        let contexts = &mut self.contexts;
+        event.describe_changes(|change| {
+            let release = change.button_change() == Some(ButtonChange::Released);
+            let mut accumulate = true;
+            for index in (0..contexts.len()).rev() {
+                let held_elsewhere = held_by_others(contexts, index);
+                let context = &mut contexts[index];
+                let enabled = context.map.is_active();
+                let capture = if enabled { captures[index] } else { None };
+                let used = context.map.apply_change(change.clone(), state, capture, accumulate, &held_elsewhere);
+                accumulate = false;
+                let consumed = match context.consume {
+                    Consume::Bound => used,
+                    Consume::All => true,
+                    Consume::Nothing => false,
+                };
+                if consumed && enabled && !release {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+impl<BI, NI, AI, VI> Default for ContextStack<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    fn default() -> ContextStack<BI, NI, AI, VI> {
+        ContextStack::new()
+    }
+}
+
+impl<BI, NI, AI, VI, S> MapEvents<S> for ContextStack<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex,
+          S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
+{
+    fn apply_event<E: DescribeInputChanges>(&mut self, event: &E, state: &mut S) {
+        self.apply(event, state);
+    }
+}
+
+impl<BI, NI, AI, VI> AdvanceFrame for ContextStack<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    fn advance_frame(&mut self) {
+        for context in &mut self.contexts {
+            context.map.advance_frame();
+        }
+    }
+}
@@ -1,6 +1,10 @@
 //! Functionality to describe input events.
 
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign, Sub};
 use super::key::Key;
+use super::mouse::{MouseButton, WheelDirection};
+use super::gamepad::{GamepadButton, GamepadAxis};
 use super::signal::Signal;
 
 /// Which representation of a key should be used (physical vs. virtual).
@@ -20,8 +24,211 @@ pub enum Keytype {
 }
 
 /// Modifier keys held down while a button is pressed.
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-pub struct Mods(u16); // or whatevs. some bitflag map
+///
+/// Every modifier (shift, ctrl, alt and super) has a left, a right and an
+/// 'either side' flag. When describing a key change, only the left and right
+/// flags are normally set. When used in a binding, the flags describe which
+/// modifiers must be held for the binding to trigger:
+///
+/// - A binding without any modifiers ignores which modifiers are held, so
+///   that `w` still triggers while shift is held to sprint.
+/// - `LEFT_CTRL` requires the left ctrl key.
+/// - `CTRL` requires at least one of the ctrl keys.
+/// - In a binding with modifiers, a modifier without any flags must *not* be
+///   held, so that `ctrl+s` and `ctrl+shift+s` can be bound to different
+///   actions. Pressing `ctrl+s` still triggers a binding to `s`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub struct Mods(u16);
+
+// Each modifier uses three bits: left, right and either side.
+const MOD_GROUPS: [u16; 4] = [0, 3, 6, 9];
+const MOD_LEFT: u16 = 0b001;
+const MOD_RIGHT: u16 = 0b010;
+const MOD_EITHER: u16 = 0b100;
+
+impl Mods {
+    /// No modifiers.
+    pub const NONE: Mods = Mods(0);
+    /// The left shift key.
+    pub const LEFT_SHIFT: Mods = Mods(MOD_LEFT);
+    /// The right shift key.
+    pub const RIGHT_SHIFT: Mods = Mods(MOD_RIGHT);
+    /// Either of the shift keys.
+    pub const SHIFT: Mods = Mods(MOD_EITHER);
+    /// The left ctrl key.
+    pub const LEFT_CTRL: Mods = Mods(MOD_LEFT << 3);
+    /// The right ctrl key.
+    pub const RIGHT_CTRL: Mods = Mods(MOD_RIGHT << 3);
+    /// Either of the ctrl keys.
+    pub const CTRL: Mods = Mods(MOD_EITHER << 3);
+    /// The left alt key.
+    pub const LEFT_ALT: Mods = Mods(MOD_LEFT << 6);
+    /// The right alt key.
+    pub const RIGHT_ALT: Mods = Mods(MOD_RIGHT << 6);
+    /// Either of the alt keys.
+    pub const ALT: Mods = Mods(MOD_EITHER << 6);
+    /// The left super (windows / command) key.
+    pub const LEFT_SUPER: Mods = Mods(MOD_LEFT << 9);
+    /// The right super (windows / command) key.
+    pub const RIGHT_SUPER: Mods = Mods(MOD_RIGHT << 9);
+    /// Either of the super (windows / command) keys.
+    pub const SUPER: Mods = Mods(MOD_EITHER << 9);
+
+    /// Returns an empty set of modifiers.
+    pub fn empty() -> Mods {
+        Mods::NONE
+    }
+
+    /// Returns the raw bits of this set.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Creates a set from raw bits (see `bits`).
+    pub fn from_bits(bits: u16) -> Mods {
+        Mods(bits)
+    }
+
+    /// Returns whether no modifiers are set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns whether all the flags of `other` are set in this set.
+    pub fn contains(&self, other: Mods) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Attempts to find the modifier with the given name.
+    ///
+    /// The names are `shift`, `ctrl`, `alt` and `super` for either side,
+    /// and the same names prefixed with `left_` or `right_` for a specific
+    /// side.
+    pub fn from_name(name: &str) -> Option<Mods> {
+        Some(match name {
+            "shift" => Mods::SHIFT,
+            "left_shift" => Mods::LEFT_SHIFT,
+            "right_shift" => Mods::RIGHT_SHIFT,
+            "ctrl" => Mods::CTRL,
+            "left_ctrl" => Mods::LEFT_CTRL,
+            "right_ctrl" => Mods::RIGHT_CTRL,
+            "alt" => Mods::ALT,
+            "left_alt" => Mods::LEFT_ALT,
+            "right_alt" => Mods::RIGHT_ALT,
+            "super" => Mods::SUPER,
+            "left_super" => Mods::LEFT_SUPER,
+            "right_super" => Mods::RIGHT_SUPER,
+            _ => return None,
+        })
+    }
+
+    /// Returns the names of the modifiers in this set, in the order
+    /// shift, ctrl, alt, super (see `from_name`).
+    pub fn names(&self) -> Vec<&'static str> {
+        let groups = [("shift", "left_shift", "right_shift"),
+                      ("ctrl", "left_ctrl", "right_ctrl"),
+                      ("alt", "left_alt", "right_alt"),
+                      ("super", "left_super", "right_super")];
+        let mut names = Vec::new();
+        for (&shift, &(either, left, right)) in MOD_GROUPS.iter().zip(groups.iter()) {
+            let flags = (self.0 >> shift) & 0b111;
+            if flags & MOD_EITHER != 0 {
+                names.push(either);
+            }
+            if flags & MOD_LEFT != 0 {
+                names.push(left);
+            }
+            if flags & MOD_RIGHT != 0 {
+                names.push(right);
+            }
+        }
+        names
+    }
+
+    /// Returns the 'either side' flags of the modifiers in this set, so that
+    /// held modifiers can be turned into a binding that accepts both sides
+    /// (ie: `LEFT_CTRL | RIGHT_SHIFT` becomes `CTRL | SHIFT`).
+    pub fn either_side(&self) -> Mods {
+        let mut mods = Mods::NONE;
+        for &shift in MOD_GROUPS.iter() {
+            if (self.0 >> shift) & 0b111 != 0 {
+                mods.0 |= MOD_EITHER << shift;
+            }
+        }
+        mods
+    }
+
+    /// Returns the modifier flag that the given key sets when held, if any.
+    pub fn of_key(key: Key) -> Mods {
+        match key {
+            Key::LeftShift => Mods::LEFT_SHIFT,
+            Key::RightShift => Mods::RIGHT_SHIFT,
+            Key::LeftCtrl => Mods::LEFT_CTRL,
+            Key::RightCtrl => Mods::RIGHT_CTRL,
+            Key::LeftAlt => Mods::LEFT_ALT,
+            Key::RightAlt => Mods::RIGHT_ALT,
+            Key::LeftSuper => Mods::LEFT_SUPER,
+            Key::RightSuper => Mods::RIGHT_SUPER,
+            _ => Mods::NONE,
+        }
+    }
+
+    /// Returns whether the modifiers in `held` satisfy the requirements
+    /// described by this set.
+    ///
+    /// An empty set is satisfied by any modifiers. Otherwise, every modifier
+    /// of the set must be held, and no other modifier may be.
+    pub fn matches(&self, held: Mods) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        MOD_GROUPS.iter().all(|&shift| {
+            let required = (self.0 >> shift) & 0b111;
+            let held = (held.0 >> shift) & 0b111;
+            if required == 0 {
+                held == 0
+            } else {
+                (required & MOD_LEFT == 0 || held & MOD_LEFT != 0) &&
+                (required & MOD_RIGHT == 0 || held & MOD_RIGHT != 0) &&
+                (required & MOD_EITHER == 0 || held != 0)
+            }
+        })
+    }
+
+    /// Returns whether some held modifiers satisfy both this set and the
+    /// other (see `matches`), so that bindings with these sets can trigger
+    /// on the same key press.
+    pub fn overlaps(&self, other: Mods) -> bool {
+        if self.is_empty() || other.is_empty() {
+            return true;
+        }
+        MOD_GROUPS.iter().all(|&shift| {
+            ((self.0 >> shift) & 0b111 == 0) == ((other.0 >> shift) & 0b111 == 0)
+        })
+    }
+}
+
+impl BitOr for Mods {
+    type Output = Mods;
+
+    fn bitor(self, other: Mods) -> Mods {
+        Mods(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Mods {
+    fn bitor_assign(&mut self, other: Mods) {
+        self.0 |= other.0;
+    }
+}
+
+impl Sub for Mods {
+    type Output = Mods;
+
+    fn sub(self, other: Mods) -> Mods {
+        Mods(self.0 & !other.0)
+    }
+}
 
 /// The description of a button on a keyboard.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -38,9 +245,9 @@ impl KeyDesc {
     /// Creates a new key description with the given key.
     pub fn new(key: Key) -> KeyDesc {
         KeyDesc {
-            key: key,
+            key,
             keytype: Keytype::Keycode,
-            mods: Mods(0),
+            mods: Mods::NONE,
         }
     }
 
@@ -51,12 +258,24 @@ impl KeyDesc {
     /// # Mini-grammar
     /// - keycode: `a`
     /// - scancode: `[a]` or `[ a ]`
+    /// - modifiers: `ctrl+s`, `ctrl + shift + s` or `alt+[q]`
+    ///   (see `Mods::from_name` for the modifier names).
     pub fn parse(mut pattern: &str) -> Option<KeyDesc> {
         pattern = pattern.trim();
-        if pattern.starts_with("[") {
+        let mut mods = Mods::NONE;
+        if let Some(last_plus) = pattern.rfind('+') {
+            for name in pattern[..last_plus].split('+') {
+                match Mods::from_name(name.trim()) {
+                    Some(m) => mods |= m,
+                    None => return None,
+                }
+            }
+            pattern = pattern[last_plus + 1..].trim();
+        }
+        let desc = if pattern.starts_with("[") {
             // Scancode
             if let Some(end) = pattern.find("]") {
-                let inner = (&pattern[1..end]).trim();
+                let inner = pattern[1..end].trim();
                 Key::from_name(inner).map(|k| KeyDesc::new(k).scancode())
             } else {
                 // Try to parse as '[' literal
@@ -65,7 +284,21 @@ impl KeyDesc {
         } else {
             // Keycode
             Key::from_name(pattern).map(KeyDesc::new)
-        }
+        };
+        desc.map(|d| d.mods(mods))
+    }
+
+    /// Returns whether a change to the key `changed` should trigger a binding
+    /// to this key description.
+    ///
+    /// The key and key type must be equal, and the modifiers held in `changed`
+    /// must satisfy the modifiers of this description (see `Mods::matches`).
+    /// A modifier key does not need to count itself as held (ie: `left_shift`
+    /// matches a change to the left shift key that reports the left shift as
+    /// held).
+    pub fn matches(&self, changed: &KeyDesc) -> bool {
+        self.key == changed.key && self.keytype == changed.keytype &&
+        self.mods.matches(changed.mods - Mods::of_key(changed.key))
     }
 
     /// Builder method to mark this input to use the scancode rather than
@@ -97,8 +330,137 @@ pub enum InputDesc {
     Key(KeyDesc),
     /// The input is some sort of signal (ie: `Quit`).
     Signal(Signal),
+    /// The input is a mouse button (ie: `Shoot`).
+    MouseButton(MouseButton),
+    /// The input is the mouse wheel scrolling in a direction (ie: `NextWeapon`).
+    Wheel(WheelDirection),
+    /// The input is a button on the gamepad with the given index (ie: `Jump`).
+    GamepadButton(u8, GamepadButton),
+    /// The input is an analog axis on the gamepad with the given index
+    /// (ie: `Throttle`).
+    GamepadAxis(u8, GamepadAxis),
+    /// The input is several keys held together (ie: `Q` and `E` for `Lean`).
+    Chord(Vec<KeyDesc>),
+    /// The input is text entry (ie: typing in a chat box).
+    Text,
+}
+
+impl InputDesc {
+    /// Returns whether the keys make a valid chord: two or more keys, none of
+    /// which is a modifier key (use modifiers on a single key instead).
+    pub fn is_valid_chord(keys: &[KeyDesc]) -> bool {
+        keys.len() >= 2 && keys.iter().all(|key| Mods::of_key(key.key).is_empty())
+    }
+
+    /// Attempts to parse an input description from the given string.
+    ///
+    /// # Mini-grammar
+    /// - keys: see `KeyDesc::parse`
+    /// - mouse buttons: `mouse:left` or `mouse:6` (see `MouseButton::from_name`)
+    /// - mouse wheel: `wheel:up`, `wheel:down`, `wheel:left` or `wheel:right`
+    /// - gamepad buttons and axes: `pad:south` for the first gamepad, or
+    ///   `pad1:left_x` for the gamepad with index 1 (see
+    ///   `GamepadButton::from_name` and `GamepadAxis::from_name`)
+    /// - chords of two or more non-modifier keys: `q & e` or `[q] & [e]`
+    /// - signals: `signal:quit_request` (see `Signal::from_name`)
+    /// - text entry: `text`
+    ///
+    /// The `Display` implementation writes descriptions in this format.
+    pub fn parse(mut pattern: &str) -> Option<InputDesc> {
+        pattern = pattern.trim();
+        if pattern.contains('&') {
+            let mut keys = Vec::new();
+            for member in pattern.split('&') {
+                match KeyDesc::parse(member) {
+                    Some(key) => keys.push(key),
+                    None => return None,
+                }
+            }
+            if !InputDesc::is_valid_chord(&keys) {
+                return None;
+            }
+            Some(InputDesc::Chord(keys))
+        } else if pattern.starts_with("pad") && pattern.contains(':') {
+            let colon = pattern.find(':').unwrap();
+            let index = match pattern["pad".len()..colon].trim() {
+                "" => 0,
+                index => {
+                    match index.parse::<u8>() {
+                        Ok(index) => index,
+                        Err(_) => return None,
+                    }
+                }
+            };
+            let name = pattern[colon + 1..].trim();
+            GamepadButton::from_name(name)
+                .map(|button| InputDesc::GamepadButton(index, button))
+                .or_else(|| GamepadAxis::from_name(name).map(|axis| InputDesc::GamepadAxis(index, axis)))
+        } else if let Some(name) = pattern.strip_prefix("signal:") {
+            Signal::from_name(name.trim()).map(InputDesc::Signal)
+        } else if let Some(name) = pattern.strip_prefix("mouse:") {
+            MouseButton::from_name(name.trim()).map(InputDesc::MouseButton)
+        } else if let Some(name) = pattern.strip_prefix("wheel:") {
+            WheelDirection::from_name(name.trim()).map(InputDesc::Wheel)
+        } else if pattern == "text" {
+            Some(InputDesc::Text)
+        } else {
+            KeyDesc::parse(pattern).map(InputDesc::Key)
+        }
+    }
+
+    /// Returns whether a change to the input `changed` should trigger a
+    /// binding to this input.
+    pub fn matches(&self, changed: &InputDesc) -> bool {
+        match (self, changed) {
+            (InputDesc::Key(bound), InputDesc::Key(changed)) => bound.matches(changed),
+            _ => self == changed,
+        }
+    }
+}
+
+impl fmt::Display for KeyDesc {
+    /// Writes the key in the format read by `KeyDesc::parse`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in self.mods.names() {
+            write!(f, "{}+", name)?;
+        }
+        match self.keytype {
+            Keytype::Keycode => write!(f, "{}", self.key.name()),
+            Keytype::Scancode => write!(f, "[{}]", self.key.name()),
+        }
+    }
+}
+
+impl fmt::Display for InputDesc {
+    /// Writes the input in the format read by `InputDesc::parse`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn pad(f: &mut fmt::Formatter, index: u8, name: &str) -> fmt::Result {
+            if index == 0 {
+                write!(f, "pad:{}", name)
+            } else {
+                write!(f, "pad{}:{}", index, name)
+            }
+        }
+        match *self {
+            InputDesc::Key(ref key) => write!(f, "{}", key),
+            InputDesc::Signal(signal) => write!(f, "signal:{}", signal.name()),
+            InputDesc::MouseButton(button) => write!(f, "mouse:{}", button.name()),
+            InputDesc::Wheel(direction) => write!(f, "wheel:{}", direction.name()),
+            InputDesc::GamepadButton(index, button) => pad(f, index, button.name()),
+            InputDesc::GamepadAxis(index, axis) => pad(f, index, axis.name()),
+            InputDesc::Chord(ref keys) => {
+                for (i, key) in keys.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " & ")?;
+                    }
+                    write!(f, "{}", key)?;
+                }
+                Ok(())
+            }
+            InputDesc::Text => write!(f, "text"),
+        }
+    }
 }
-// TODO: Handle modifier checks, somehow (in InputMap?)
 
 impl From<KeyDesc> for InputDesc {
     fn from(keydesc: KeyDesc) -> InputDesc {
@@ -117,3 +479,116 @@ impl From<Signal> for InputDesc {
         InputDesc::Signal(signal)
     }
 }
+
+impl From<MouseButton> for InputDesc {
+    fn from(button: MouseButton) -> InputDesc {
+        InputDesc::MouseButton(button)
+    }
+}
+
+impl From<WheelDirection> for InputDesc {
+    fn from(direction: WheelDirection) -> InputDesc {
+        InputDesc::Wheel(direction)
+    }
+}
+
+impl From<GamepadButton> for InputDesc {
+    fn from(button: GamepadButton) -> InputDesc {
+        InputDesc::GamepadButton(0, button)
+    }
+}
+
+impl From<GamepadAxis> for InputDesc {
+    fn from(axis: GamepadAxis) -> InputDesc {
+        InputDesc::GamepadAxis(0, axis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keytype, Mods, KeyDesc, InputDesc};
+    use super::super::key::Key;
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(KeyDesc::parse("a"), Some(KeyDesc::new(Key::A)));
+        assert_eq!(KeyDesc::parse(" [ a ] "), Some(KeyDesc::new(Key::A).scancode()));
+        assert_eq!(KeyDesc::parse("space").map(|d| d.keytype), Some(Keytype::Keycode));
+        assert_eq!(KeyDesc::parse("nope"), None);
+        assert_eq!(KeyDesc::parse("[nope]"), None);
+        assert_eq!(KeyDesc::parse("[other(5)]"), Some(KeyDesc::new(Key::Other(5)).scancode()));
+        assert_eq!(KeyDesc::parse("other(x)"), None);
+        assert_eq!(InputDesc::parse("text"), Some(InputDesc::Text));
+    }
+
+    #[test]
+    fn parse_modifiers() {
+        assert_eq!(KeyDesc::parse("ctrl+s"), Some(KeyDesc::new(Key::S).mods(Mods::CTRL)));
+        assert_eq!(KeyDesc::parse("ctrl + shift + s"),
+                   Some(KeyDesc::new(Key::S).mods(Mods::CTRL | Mods::SHIFT)));
+        assert_eq!(KeyDesc::parse("left_alt+[q]"),
+                   Some(KeyDesc::new(Key::Q).scancode().mods(Mods::LEFT_ALT)));
+        assert_eq!(KeyDesc::parse("hyper+s"), None);
+        assert_eq!(KeyDesc::parse("ctrl+"), None);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in &["a", "[a]", "ctrl+s", "shift+left_alt+[f1]", "right_super+space"] {
+            let desc = KeyDesc::parse(text).unwrap();
+            assert_eq!(&desc.to_string(), text);
+            assert_eq!(KeyDesc::parse(&desc.to_string()), Some(desc));
+        }
+    }
+
+    #[test]
+    fn unmodified_bindings_ignore_modifiers() {
+        let w = KeyDesc::new(Key::W);
+        assert!(w.matches(&KeyDesc::new(Key::W)));
+        assert!(w.matches(&KeyDesc::new(Key::W).mods(Mods::LEFT_SHIFT)));
+        assert!(w.matches(&KeyDesc::new(Key::W).mods(Mods::RIGHT_CTRL | Mods::LEFT_ALT)));
+        assert!(!w.matches(&KeyDesc::new(Key::W).scancode()));
+        assert!(!w.matches(&KeyDesc::new(Key::S)));
+    }
+
+    #[test]
+    fn modified_bindings_match_exactly() {
+        let save = KeyDesc::new(Key::S).mods(Mods::CTRL);
+        assert!(save.matches(&KeyDesc::new(Key::S).mods(Mods::LEFT_CTRL)));
+        assert!(save.matches(&KeyDesc::new(Key::S).mods(Mods::RIGHT_CTRL)));
+        assert!(!save.matches(&KeyDesc::new(Key::S)));
+        assert!(!save.matches(&KeyDesc::new(Key::S).mods(Mods::LEFT_CTRL | Mods::LEFT_SHIFT)));
+
+        let left = KeyDesc::new(Key::S).mods(Mods::LEFT_CTRL);
+        assert!(left.matches(&KeyDesc::new(Key::S).mods(Mods::LEFT_CTRL)));
+        assert!(!left.matches(&KeyDesc::new(Key::S).mods(Mods::RIGHT_CTRL)));
+    }
+
+    #[test]
+    fn modifier_keys_match_themselves() {
+        let shift = KeyDesc::new(Key::LeftShift).mods(Mods::LEFT_CTRL);
+        let change = KeyDesc::new(Key::LeftShift).mods(Mods::LEFT_CTRL | Mods::LEFT_SHIFT);
+        assert!(shift.matches(&change));
+        assert!(KeyDesc::new(Key::LeftShift).matches(&change));
+    }
+
+    #[test]
+    fn parse_chords() {
+        assert_eq!(InputDesc::parse("q & [e]"),
+                   Some(InputDesc::Chord(vec![KeyDesc::new(Key::Q), KeyDesc::new(Key::E).scancode()])));
+        assert_eq!(InputDesc::parse("q &"), None);
+        assert_eq!(InputDesc::parse("q"), Some(InputDesc::Key(KeyDesc::new(Key::Q))));
+        assert_eq!(InputDesc::parse("left_shift & q"), None);
+        assert!(!InputDesc::is_valid_chord(&[KeyDesc::new(Key::Q)]));
+        assert!(!InputDesc::is_valid_chord(&[KeyDesc::new(Key::LeftCtrl), KeyDesc::new(Key::Q)]));
+    }
+
+    #[test]
+    fn overlapping_modifiers() {
+        assert!(Mods::NONE.overlaps(Mods::CTRL));
+        assert!(Mods::CTRL.overlaps(Mods::LEFT_CTRL));
+        assert!(Mods::LEFT_CTRL.overlaps(Mods::RIGHT_CTRL));
+        assert!(!Mods::CTRL.overlaps(Mods::CTRL | Mods::SHIFT));
+        assert!(!Mods::CTRL.overlaps(Mods::ALT));
+    }
+}
@@ -0,0 +1,364 @@
+//! Compact binary encoding of input states.
+
+use std::error::Error;
+use std::fmt;
+use super::state::{InputState, NamedIndex, ButtonValue};
+
+/// The version of the encoding, written in the header of encoded states.
+const ENCODING_VERSION: u32 = 2;
+
+/// Adds the bytes to a 32-bit FNV-1a hash.
+fn hash_bytes(mut hash: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        hash = (hash ^ byte as u32).wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// Adds the names of the actions of one kind, in declaration order, to the
+/// hash. Each name is followed by a 0 byte, and the kind by a 1 byte.
+fn hash_names<I: NamedIndex>(hash: u32) -> u32 {
+    let hash = I::all().iter().fold(hash, |hash, id| {
+        hash_bytes(hash_bytes(hash, id.name().as_bytes()), &[0])
+    });
+    hash_bytes(hash, &[1])
+}
+
+/// Returns a hash of the names of the actions of the state, which is the same
+/// across builds and platforms.
+fn actions_hash<S: InputState>() -> u32 {
+    let hash = hash_names::<S::ButtonId>(0x811c_9dc5);
+    let hash = hash_names::<S::SignalId>(hash);
+    let hash = hash_names::<S::AxisId>(hash);
+    hash_names::<S::VectorId>(hash)
+}
+
+/// The encoded data was written by another version of the encoding, or for
+/// a state with different actions, or it ended before the whole state was
+/// decoded, or had data left over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError;
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the encoded input state doesn't match the input state")
+    }
+}
+
+impl Error for DecodeError {
+    fn description(&self) -> &str {
+        "invalid encoded input state"
+    }
+}
+
+/// Writes values as a sequence of bits.
+#[derive(Debug, Clone, Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    /// The number of bits used in the last byte (0 if it is full).
+    used: u8,
+}
+
+impl BitWriter {
+    /// Creates an empty writer.
+    pub fn new() -> BitWriter {
+        BitWriter::default()
+    }
+
+    /// Returns the written bits, padded with zeros to a whole byte.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Writes a single bit.
+    pub fn write_bit(&mut self, bit: bool) {
+        if self.used == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 1 << self.used;
+        }
+        self.used = (self.used + 1) % 8;
+    }
+
+    /// Writes the lowest `count` bits of the value.
+    pub fn write_bits(&mut self, value: u32, count: u8) {
+        for i in 0..count {
+            self.write_bit(value & (1 << i) != 0);
+        }
+    }
+
+    /// Writes a number in groups of 7 bits, each followed by a bit telling
+    /// whether more follow.
+    fn write_count(&mut self, mut value: u32) {
+        loop {
+            self.write_bits(value & 0x7f, 7);
+            value >>= 7;
+            self.write_bit(value != 0);
+            if value == 0 {
+                return;
+            }
+        }
+    }
+
+    /// Writes the header of an encoded state: the version of the encoding,
+    /// the number of actions of each kind, and a hash of their names.
+    fn write_header<S: InputState>(&mut self) {
+        self.write_count(ENCODING_VERSION);
+        self.write_count(S::ButtonId::all().len() as u32);
+        self.write_count(S::SignalId::all().len() as u32);
+        self.write_count(S::AxisId::all().len() as u32);
+        self.write_count(S::VectorId::all().len() as u32);
+        self.write_bits(actions_hash::<S>(), 32);
+    }
+
+    /// Writes the encoded parts of a button.
+    pub fn write_button(&mut self, button: &ButtonValue) {
+        self.write_bit(button.held);
+        self.write_bit(button.pressed);
+        self.write_bit(button.released);
+        self.write_bit(button.double_pressed);
+        let counted = button.press_count != button.pressed as u8 ||
+                      button.release_count != button.released as u8;
+        self.write_bit(counted);
+        if counted {
+            self.write_bits(button.press_count as u32, 8);
+            self.write_bits(button.release_count as u32, 8);
+        }
+        self.write_bit(button.repeats > 0);
+        if button.repeats > 0 {
+            self.write_bits(button.repeats as u32, 8);
+        }
+    }
+
+    /// Writes a button if it changed since the previous frame.
+    pub fn write_button_delta(&mut self, button: &ButtonValue, previous: &ButtonValue) {
+        let changed = button.held != previous.held || button.pressed != previous.pressed ||
+                      button.released != previous.released ||
+                      button.double_pressed != previous.double_pressed ||
+                      button.press_count != previous.press_count ||
+                      button.release_count != previous.release_count ||
+                      button.repeats != previous.repeats;
+        self.write_bit(changed);
+        if changed {
+            self.write_button(button);
+        }
+    }
+
+    /// Writes a number.
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_bits(value.to_bits(), 32);
+    }
+
+    /// Writes a number if it changed since the previous frame.
+    pub fn write_f32_delta(&mut self, value: f32, previous: f32) {
+        let changed = value.to_bits() != previous.to_bits();
+        self.write_bit(changed);
+        if changed {
+            self.write_f32(value);
+        }
+    }
+}
+
+/// Reads values written by a `BitWriter`.
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    /// The index of the next bit.
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a reader for the bytes.
+    pub fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            bytes,
+            position: 0,
+        }
+    }
+
+    /// Checks that only the padding of the last byte is left.
+    pub fn finish(&self) -> Result<(), DecodeError> {
+        if self.position.div_ceil(8) == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(DecodeError)
+        }
+    }
+
+    /// Reads a single bit.
+    pub fn read_bit(&mut self) -> Result<bool, DecodeError> {
+        let byte = match self.bytes.get(self.position / 8) {
+            Some(&byte) => byte,
+            None => return Err(DecodeError),
+        };
+        let bit = byte & (1 << (self.position % 8)) != 0;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    /// Reads a value of `count` bits.
+    pub fn read_bits(&mut self, count: u8) -> Result<u32, DecodeError> {
+        let mut value = 0;
+        for i in 0..count {
+            if self.read_bit()? {
+                value |= 1 << i;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Reads a number written by `write_count`.
+    fn read_count(&mut self) -> Result<u32, DecodeError> {
+        let mut value = 0;
+        for i in 0..5 {
+            value |= self.read_bits(7)? << (7 * i);
+            if !self.read_bit()? {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError)
+    }
+
+    /// Reads the header of an encoded state, and checks that it matches the
+    /// state.
+    fn read_header<S: InputState>(&mut self) -> Result<(), DecodeError> {
+        let expected = [ENCODING_VERSION,
+                        S::ButtonId::all().len() as u32,
+                        S::SignalId::all().len() as u32,
+                        S::AxisId::all().len() as u32,
+                        S::VectorId::all().len() as u32];
+        for &value in &expected {
+            if self.read_count()? != value {
+                return Err(DecodeError);
+            }
+        }
+        if self.read_bits(32)? != actions_hash::<S>() {
+            return Err(DecodeError);
+        }
+        Ok(())
+    }
+
+    /// Reads the encoded parts of a button into the button.
+    pub fn read_button(&mut self, button: &mut ButtonValue) -> Result<(), DecodeError> {
+        button.held = self.read_bit()?;
+        button.pressed = self.read_bit()?;
+        button.released = self.read_bit()?;
+        button.double_pressed = self.read_bit()?;
+        if self.read_bit()? {
+            button.press_count = self.read_bits(8)? as u8;
+            button.release_count = self.read_bits(8)? as u8;
+        } else {
+            button.press_count = button.pressed as u8;
+            button.release_count = button.released as u8;
+        }
+        button.repeats = if self.read_bit()? { self.read_bits(8)? as u8 } else { 0 };
+        Ok(())
+    }
+
+    /// Reads a button written with `write_button_delta`.
+    pub fn read_button_delta(&mut self,
+                             button: &mut ButtonValue,
+                             previous: &ButtonValue)
+                             -> Result<(), DecodeError> {
+        if self.read_bit()? {
+            self.read_button(button)
+        } else {
+            button.held = previous.held;
+            button.pressed = previous.pressed;
+            button.released = previous.released;
+            button.double_pressed = previous.double_pressed;
+            button.press_count = previous.press_count;
+            button.release_count = previous.release_count;
+            button.repeats = previous.repeats;
+            Ok(())
+        }
+    }
+
+    /// Reads a number.
+    pub fn read_f32(&mut self) -> Result<f32, DecodeError> {
+        self.read_bits(32).map(f32::from_bits)
+    }
+
+    /// Reads a number written with `write_f32_delta`.
+    pub fn read_f32_delta(&mut self, previous: f32) -> Result<f32, DecodeError> {
+        if self.read_bit()? {
+            self.read_f32()
+        } else {
+            Ok(previous)
+        }
+    }
+}
+
+/// An input state that can be encoded in a few bytes (ie: to send it over the
+/// network, or to save it in a replay).
+///
+/// The `input!` macro implements this for the input states it generates.
+/// The full encoding starts with a header holding its version, the number of
+/// actions of each kind and a hash of their names (usually 9 bytes), so that
+/// data encoded for another state fails to decode. It then packs every
+/// action, in declaration order, into a bitfield:
+///
+/// - Buttons use 6 bits: held, pressed, released, double pressed, whether
+///   the button was pressed or released more than once in the frame, in
+///   which case both counts follow (8 bits each), and whether it repeated,
+///   in which case the number of repeats follows (8 bits).
+/// - Signals use 1 bit.
+/// - Axes use 32 bits, and vectors 64 bits.
+///
+/// The delta encoding adds a bit per button, axis and vector component
+/// telling whether it changed since the previous frame, and leaves it out if
+/// it didn't. Signals are written as they are. Since most actions don't
+/// change in most frames, this is usually much smaller. Deltas have no
+/// header, so that they stay a few bytes long: they should only be decoded
+/// against previous states that come from a full encoding (directly, or
+/// through earlier deltas), which checks the header once.
+///
+/// The encoding only depends on the list of actions, so it is the same
+/// across builds as long as the actions are the same and in the same order.
+/// Hold times, buffered presses, the mouse wheel and text are not encoded.
+///
+/// If decoding fails, the state may have been partly changed.
+pub trait EncodeState: InputState + Sized {
+    /// Writes the state, without the header.
+    fn encode_bits(&self, bits: &mut BitWriter);
+
+    /// Reads a state written by `encode_bits` into this state.
+    fn decode_bits(&mut self, bits: &mut BitReader) -> Result<(), DecodeError>;
+
+    /// Writes the changes since the previous state.
+    fn encode_delta_bits(&self, previous: &Self, bits: &mut BitWriter);
+
+    /// Reads a state written by `encode_delta_bits` into this state.
+    fn decode_delta_bits(&mut self, previous: &Self, bits: &mut BitReader) -> Result<(), DecodeError>;
+
+    /// Returns the encoded state.
+    fn encode(&self) -> Vec<u8> {
+        let mut bits = BitWriter::new();
+        bits.write_header::<Self>();
+        self.encode_bits(&mut bits);
+        bits.into_bytes()
+    }
+
+    /// Reads an encoded state into this state.
+    fn decode(&mut self, bytes: &[u8]) -> Result<(), DecodeError> {
+        let mut bits = BitReader::new(bytes);
+        bits.read_header::<Self>()?;
+        self.decode_bits(&mut bits)?;
+        bits.finish()
+    }
+
+    /// Returns the changes since the previous state, encoded.
+    fn encode_delta(&self, previous: &Self) -> Vec<u8> {
+        let mut bits = BitWriter::new();
+        self.encode_delta_bits(previous, &mut bits);
+        bits.into_bytes()
+    }
+
+    /// Reads the encoded changes since the previous state into this state.
+    fn decode_delta(&mut self, previous: &Self, bytes: &[u8]) -> Result<(), DecodeError> {
+        let mut bits = BitReader::new(bytes);
+        self.decode_delta_bits(previous, &mut bits)?;
+        bits.finish()
+    }
+}
@@ -0,0 +1,147 @@
+//! Gamepad button and axis values.
+
+/// A button on a gamepad.
+///
+/// The face buttons are named by their position, since their labels differ
+/// between controllers (ie: `South` is 'A' on an Xbox controller and 'Cross'
+/// on a PlayStation controller).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum GamepadButton {
+    /// The bottom face button.
+    South,
+    /// The right face button.
+    East,
+    /// The left face button.
+    West,
+    /// The top face button.
+    North,
+    /// The left shoulder button (bumper).
+    LeftShoulder,
+    /// The right shoulder button (bumper).
+    RightShoulder,
+    /// The left trigger, used as a digital button.
+    LeftTrigger,
+    /// The right trigger, used as a digital button.
+    RightTrigger,
+    /// Up on the directional pad.
+    DPadUp,
+    /// Down on the directional pad.
+    DPadDown,
+    /// Left on the directional pad.
+    DPadLeft,
+    /// Right on the directional pad.
+    DPadRight,
+    /// The start (or 'menu' / 'options') button.
+    Start,
+    /// The back (or 'select' / 'view' / 'share') button.
+    Back,
+    /// The guide (or 'home') button.
+    Guide,
+    /// Clicking the left stick.
+    LeftStick,
+    /// Clicking the right stick.
+    RightStick,
+}
+
+impl GamepadButton {
+    /// Returns the name of this button.
+    pub fn name(&self) -> &'static str {
+        use self::GamepadButton::*;
+        match *self {
+            South => "south",
+            East => "east",
+            West => "west",
+            North => "north",
+            LeftShoulder => "left_shoulder",
+            RightShoulder => "right_shoulder",
+            LeftTrigger => "left_trigger",
+            RightTrigger => "right_trigger",
+            DPadUp => "dpad_up",
+            DPadDown => "dpad_down",
+            DPadLeft => "dpad_left",
+            DPadRight => "dpad_right",
+            Start => "start",
+            Back => "back",
+            Guide => "guide",
+            LeftStick => "left_stick",
+            RightStick => "right_stick",
+        }
+    }
+
+    /// Attempts to find a gamepad button with the given name.
+    pub fn from_name(name: &str) -> Option<GamepadButton> {
+        use self::GamepadButton::*;
+        Some(match name {
+            "south" => South,
+            "east" => East,
+            "west" => West,
+            "north" => North,
+            "left_shoulder" => LeftShoulder,
+            "right_shoulder" => RightShoulder,
+            "left_trigger" => LeftTrigger,
+            "right_trigger" => RightTrigger,
+            "dpad_up" => DPadUp,
+            "dpad_down" => DPadDown,
+            "dpad_left" => DPadLeft,
+            "dpad_right" => DPadRight,
+            "start" => Start,
+            "back" => Back,
+            "guide" => Guide,
+            "left_stick" => LeftStick,
+            "right_stick" => RightStick,
+            _ => return None,
+        })
+    }
+}
+
+/// An analog axis on a gamepad.
+///
+/// Stick axes range from -1 to 1, with positive values to the right and down.
+/// Trigger axes range from 0 (released) to 1 (fully pressed).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum GamepadAxis {
+    /// The horizontal axis of the left stick.
+    LeftX,
+    /// The vertical axis of the left stick.
+    LeftY,
+    /// The horizontal axis of the right stick.
+    RightX,
+    /// The vertical axis of the right stick.
+    RightY,
+    /// The left trigger.
+    LeftTrigger,
+    /// The right trigger.
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// Returns the name of this axis.
+    pub fn name(&self) -> &'static str {
+        use self::GamepadAxis::*;
+        match *self {
+            LeftX => "left_x",
+            LeftY => "left_y",
+            RightX => "right_x",
+            RightY => "right_y",
+            LeftTrigger => "left_trigger_axis",
+            RightTrigger => "right_trigger_axis",
+        }
+    }
+
+    /// Attempts to find a gamepad axis with the given name.
+    ///
+    /// The trigger axes are called `left_trigger_axis` and `right_trigger_axis`
+    /// to tell them apart from the trigger buttons.
+    pub fn from_name(name: &str) -> Option<GamepadAxis> {
+        use self::GamepadAxis::*;
+        Some(match name {
+            "left_x" => LeftX,
+            "left_y" => LeftY,
+            "right_x" => RightX,
+            "right_y" => RightY,
+            "left_trigger_axis" => LeftTrigger,
+            "right_trigger_axis" => RightTrigger,
+            _ => return None,
+        })
+    }
+}
@@ -28,18 +28,25 @@ macro_rules! key {
                     $(
                         self::Key::$key => Cow::Borrowed($name),
                     )*
-                    self::Key::Other(val) => format!("Other({})", val).into(),
+                    self::Key::Other(val) => format!("other({})", val).into(),
                 }
             }
             
             /// Attempts to find a key with the given name.
-            /// All key names are lowercased.
+            /// All key names are lowercased, and keys that aren't in dalgi
+            /// are named by their number (ie: `other(1073742106)`).
             pub fn from_name(name: &str) -> Option<Key> {
                 Some(match name {
                     $(
                         $name => self::Key::$key ,
                     )*
-                    _ => return None,
+                    _ => {
+                        let number = name.strip_prefix("other(").and_then(|rest| rest.strip_suffix(')'));
+                        match number.map(|number| number.parse::<i32>()) {
+                            Some(Ok(number)) => self::Key::Other(number),
+                            _ => return None,
+                        }
+                    }
                 })
             }
         }
@@ -61,31 +68,251 @@ macro_rules! key {
 
 key! {
     /// The left arrow.
-    key Left    => "left",
+    key Left         => "left",
     /// The right arrow.
-    key Right   => "right",
+    key Right        => "right",
     /// The up arrow.
-    key Up      => "up",
-    /// The left arrow.
-    key Down    => "down",
+    key Up           => "up",
+    /// The down arrow.
+    key Down         => "down",
     /// The space bar.
-    key Space   => "space",
+    key Space        => "space",
     /// The `return` or `enter` button.
-    key Return  => "return",
+    key Return       => "return",
+    /// The tab key.
+    key Tab          => "tab",
+    /// The backspace key.
+    key Backspace    => "backspace",
+    /// The escape key.
+    key Escape       => "escape",
+    /// The delete key.
+    key Delete       => "delete",
+    /// The insert key.
+    key Insert       => "insert",
+    /// The home key.
+    key Home         => "home",
+    /// The end key.
+    key End          => "end",
+    /// The page up key.
+    key PageUp       => "page_up",
+    /// The page down key.
+    key PageDown     => "page_down",
+    /// The number 0.
+    key Zero         => "0",
     /// The number 1.
-    key One     => "1",
+    key One          => "1",
     /// The number 2.
-    key Two     => "2",
+    key Two          => "2",
     /// The number 3.
-    key Three   => "3",
+    key Three        => "3",
     /// The number 4.
-    key Four    => "4",
-    /// The letter w.
-    key W => "w",
+    key Four         => "4",
+    /// The number 5.
+    key Five         => "5",
+    /// The number 6.
+    key Six          => "6",
+    /// The number 7.
+    key Seven        => "7",
+    /// The number 8.
+    key Eight        => "8",
+    /// The number 9.
+    key Nine         => "9",
     /// The letter a.
-    key A => "a",
-    /// The letter s.
-    key S => "s",
+    key A            => "a",
+    /// The letter b.
+    key B            => "b",
+    /// The letter c.
+    key C            => "c",
     /// The letter d.
-    key D => "d",
+    key D            => "d",
+    /// The letter e.
+    key E            => "e",
+    /// The letter f.
+    key F            => "f",
+    /// The letter g.
+    key G            => "g",
+    /// The letter h.
+    key H            => "h",
+    /// The letter i.
+    key I            => "i",
+    /// The letter j.
+    key J            => "j",
+    /// The letter k.
+    key K            => "k",
+    /// The letter l.
+    key L            => "l",
+    /// The letter m.
+    key M            => "m",
+    /// The letter n.
+    key N            => "n",
+    /// The letter o.
+    key O            => "o",
+    /// The letter p.
+    key P            => "p",
+    /// The letter q.
+    key Q            => "q",
+    /// The letter r.
+    key R            => "r",
+    /// The letter s.
+    key S            => "s",
+    /// The letter t.
+    key T            => "t",
+    /// The letter u.
+    key U            => "u",
+    /// The letter v.
+    key V            => "v",
+    /// The letter w.
+    key W            => "w",
+    /// The letter x.
+    key X            => "x",
+    /// The letter y.
+    key Y            => "y",
+    /// The letter z.
+    key Z            => "z",
+    /// The minus / hyphen key (`-`).
+    key Minus        => "minus",
+    /// The equals key (`=`).
+    key Equals       => "equals",
+    /// The left bracket key (`[`).
+    key LeftBracket  => "left_bracket",
+    /// The right bracket key (`]`).
+    key RightBracket => "right_bracket",
+    /// The backslash key (`\`).
+    key Backslash    => "backslash",
+    /// The semicolon key (`;`).
+    key Semicolon    => "semicolon",
+    /// The apostrophe / quote key (`'`).
+    key Apostrophe   => "apostrophe",
+    /// The grave accent / backtick key (`` ` ``).
+    key Grave        => "grave",
+    /// The comma key (`,`).
+    key Comma        => "comma",
+    /// The period key (`.`).
+    key Period       => "period",
+    /// The slash key (`/`).
+    key Slash        => "slash",
+    /// Function key 1.
+    key F1           => "f1",
+    /// Function key 2.
+    key F2           => "f2",
+    /// Function key 3.
+    key F3           => "f3",
+    /// Function key 4.
+    key F4           => "f4",
+    /// Function key 5.
+    key F5           => "f5",
+    /// Function key 6.
+    key F6           => "f6",
+    /// Function key 7.
+    key F7           => "f7",
+    /// Function key 8.
+    key F8           => "f8",
+    /// Function key 9.
+    key F9           => "f9",
+    /// Function key 10.
+    key F10          => "f10",
+    /// Function key 11.
+    key F11          => "f11",
+    /// Function key 12.
+    key F12          => "f12",
+    /// Function key 13.
+    key F13          => "f13",
+    /// Function key 14.
+    key F14          => "f14",
+    /// Function key 15.
+    key F15          => "f15",
+    /// Function key 16.
+    key F16          => "f16",
+    /// Function key 17.
+    key F17          => "f17",
+    /// Function key 18.
+    key F18          => "f18",
+    /// Function key 19.
+    key F19          => "f19",
+    /// Function key 20.
+    key F20          => "f20",
+    /// Function key 21.
+    key F21          => "f21",
+    /// Function key 22.
+    key F22          => "f22",
+    /// Function key 23.
+    key F23          => "f23",
+    /// Function key 24.
+    key F24          => "f24",
+    /// The number 0 on the numpad.
+    key KpZero       => "kp_0",
+    /// The number 1 on the numpad.
+    key KpOne        => "kp_1",
+    /// The number 2 on the numpad.
+    key KpTwo        => "kp_2",
+    /// The number 3 on the numpad.
+    key KpThree      => "kp_3",
+    /// The number 4 on the numpad.
+    key KpFour       => "kp_4",
+    /// The number 5 on the numpad.
+    key KpFive       => "kp_5",
+    /// The number 6 on the numpad.
+    key KpSix        => "kp_6",
+    /// The number 7 on the numpad.
+    key KpSeven      => "kp_7",
+    /// The number 8 on the numpad.
+    key KpEight      => "kp_8",
+    /// The number 9 on the numpad.
+    key KpNine       => "kp_9",
+    /// The numpad divide key.
+    key KpDivide     => "kp_divide",
+    /// The numpad multiply key.
+    key KpMultiply   => "kp_multiply",
+    /// The numpad minus key.
+    key KpMinus      => "kp_minus",
+    /// The numpad plus key.
+    key KpPlus       => "kp_plus",
+    /// The numpad enter key.
+    key KpEnter      => "kp_enter",
+    /// The numpad period / decimal key.
+    key KpPeriod     => "kp_period",
+    /// The numpad equals key.
+    key KpEquals     => "kp_equals",
+    /// The caps lock key.
+    key CapsLock     => "caps_lock",
+    /// The num lock key.
+    key NumLock      => "num_lock",
+    /// The scroll lock key.
+    key ScrollLock   => "scroll_lock",
+    /// The print screen key.
+    key PrintScreen  => "print_screen",
+    /// The pause / break key.
+    key Pause        => "pause",
+    /// The context menu / application key.
+    key Menu         => "menu",
+    /// The mute key.
+    key Mute         => "mute",
+    /// The volume up key.
+    key VolumeUp     => "volume_up",
+    /// The volume down key.
+    key VolumeDown   => "volume_down",
+    /// The media play / pause key.
+    key MediaPlay    => "media_play",
+    /// The media stop key.
+    key MediaStop    => "media_stop",
+    /// The media 'next track' key.
+    key MediaNext    => "media_next",
+    /// The media 'previous track' key.
+    key MediaPrev    => "media_prev",
+    /// The left shift key.
+    key LeftShift    => "left_shift",
+    /// The left control key.
+    key LeftCtrl     => "left_ctrl",
+    /// The left alt / option key.
+    key LeftAlt      => "left_alt",
+    /// The left super / windows / command key.
+    key LeftSuper    => "left_super",
+    /// The right shift key.
+    key RightShift   => "right_shift",
+    /// The right control key.
+    key RightCtrl    => "right_ctrl",
+    /// The right alt / option key.
+    key RightAlt     => "right_alt",
+    /// The right super / windows / command key.
+    key RightSuper   => "right_super",
 }
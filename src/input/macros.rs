@@ -2,9 +2,64 @@
 
 /// Generates a set of structs and enums to model an input state.
 ///
+/// The `[axis]` and `[vector]` sections are optional. Without them, the state
+/// uses `dalgi::input::NoInputs` as its axis or vector id.
+///
+/// The optional `[wheel]` and `[text]` sections name fields that accumulate
+/// the mouse wheel movement and the text entered in each frame (see
+/// `InputState::get_wheel` and `InputState::get_text`):
+///
+/// ```rust,ignore
+/// [wheel]
+/// pub wheel,
+///
+/// [text]
+/// pub text,
+/// ```
+///
+/// The id enums list their members in `ALL`, and implement
+/// `dalgi::input::NamedIndex` using the member names (ie: `ButtonId::jump`
+/// is named "jump").
+///
+/// The input state implements `dalgi::input::EncodeState`, to encode the
+/// action state of a frame in a few bytes.
+///
 /// For a rough view of what is generated, see `examples/ex_macroless.rs`.
 #[macro_export]
 macro_rules! input {
+    (@id) => { dalgi::input::NoInputs };
+    (@id $id:ident) => { self::$id };
+    (@field $self_:ident) => { None };
+    (@field $self_:ident $field:ident) => { Some(&mut $self_.$field) };
+    (@named $id:ident { $( $name:ident , )* }) => {
+        impl $id {
+            /// Every member of the enum, in declaration order [macro_generated].
+            pub const ALL: &'static [$id] = &[ $( $id::$name , )* ];
+        }
+
+        impl dalgi::input::NamedIndex for $id {
+            fn all() -> &'static [$id] {
+                $id::ALL
+            }
+
+            fn name(&self) -> &'static str {
+                match *self {
+                    $(
+                        $id::$name => stringify!($name) ,
+                    )*
+                }
+            }
+
+            fn from_name(name: &str) -> Option<$id> {
+                match name {
+                    $(
+                        stringify!($name) => Some($id::$name) ,
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    };
     (
         pub struct $input_type:ident {
             [ button ]
@@ -20,6 +75,30 @@ macro_rules! input {
                     $signal:ident ,
                 )*
             }
+            $(
+                [ axis ]
+                pub struct $axis_type:ident < $axis_id:ident > {
+                    $(
+                        $axis:ident ,
+                    )*
+                }
+            )?
+            $(
+                [ vector ]
+                pub struct $vector_type:ident < $vector_id:ident > {
+                    $(
+                        $vector:ident ,
+                    )*
+                }
+            )?
+            $(
+                [ wheel ]
+                pub $wheel:ident ,
+            )?
+            $(
+                [ text ]
+                pub $text:ident ,
+            )?
         }
     ) => {
         /// The state of a set of button-style inputs [macro-generated].
@@ -38,6 +117,8 @@ macro_rules! input {
                 $button ,
             )*
         }
+
+        input!(@named $button_id { $( $button , )* });
         
         /// The state of a set of signal-style inputs [macro-generated].
         #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -55,12 +136,68 @@ macro_rules! input {
                 $signal ,
             )*
         }
+
+        input!(@named $signal_id { $( $signal , )* });
+        
+        $(
+            /// The state of a set of axis-style inputs [macro-generated].
+            #[derive(Debug, Clone, PartialEq, Eq, Default)]
+            pub struct $axis_type {
+                $(
+                    pub $axis : dalgi::input::AxisValue ,
+                )*
+            }
+
+            /// The identifier of a member of the $axis_type struct [macro_generated].
+            #[allow(non_camel_case_types)]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            pub enum $axis_id {
+                $(
+                    $axis ,
+                )*
+            }
+
+            input!(@named $axis_id { $( $axis , )* });
+        )?
+        
+        $(
+            /// The state of a set of vector-style inputs [macro-generated].
+            #[derive(Debug, Clone, PartialEq, Eq, Default)]
+            pub struct $vector_type {
+                $(
+                    pub $vector : dalgi::input::VectorValue ,
+                )*
+            }
+
+            /// The identifier of a member of the $vector_type struct [macro_generated].
+            #[allow(non_camel_case_types)]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            pub enum $vector_id {
+                $(
+                    $vector ,
+                )*
+            }
+
+            input!(@named $vector_id { $( $vector , )* });
+        )?
         
         /// An input state which can be used with an event map [macro_generated].
         #[derive(Debug, Clone, PartialEq, Eq, Default)]
         pub struct $input_type {
             pub button: self::$button_type,
             pub signal: self::$signal_type,
+            $(
+                pub axis: self::$axis_type,
+            )?
+            $(
+                pub vector: self::$vector_type,
+            )?
+            $(
+                pub $wheel: dalgi::input::WheelValue,
+            )?
+            $(
+                pub $text: dalgi::input::TextValue,
+            )?
         }
         
         impl $input_type {
@@ -78,12 +215,30 @@ macro_rules! input {
                 $(
                     self.signal.$signal.advance_frame();
                 )*
+                $(
+                    $(
+                        self.axis.$axis.advance_frame();
+                    )*
+                )?
+                $(
+                    $(
+                        self.vector.$vector.advance_frame();
+                    )*
+                )?
+                $(
+                    self.$wheel.advance_frame();
+                )?
+                $(
+                    self.$text.advance_frame();
+                )?
             }
         }
         
         impl dalgi::input::InputState for $input_type {
             type ButtonId = self::$button_id;
             type SignalId = self::$signal_id;
+            type AxisId = input!(@id $( $axis_id )?);
+            type VectorId = input!(@id $( $vector_id )?);
             
             fn get_button<'a>(&'a mut self, id: &Self::ButtonId) -> &'a mut dalgi::input::ButtonValue {
                 match *id {
@@ -100,6 +255,119 @@ macro_rules! input {
                     )*
                 }
             }
+
+            fn get_axis<'a>(&'a mut self, id: &Self::AxisId) -> &'a mut dalgi::input::AxisValue {
+                match *id {
+                    $(
+                        $(
+                            self::$axis_id::$axis => &mut self.axis.$axis ,
+                        )*
+                    )?
+                }
+            }
+
+            fn get_vector<'a>(&'a mut self, id: &Self::VectorId) -> &'a mut dalgi::input::VectorValue {
+                match *id {
+                    $(
+                        $(
+                            self::$vector_id::$vector => &mut self.vector.$vector ,
+                        )*
+                    )?
+                }
+            }
+
+            fn get_wheel(&mut self) -> Option<&mut dalgi::input::WheelValue> {
+                input!(@field self $( $wheel )?)
+            }
+
+            fn get_text(&mut self) -> Option<&mut dalgi::input::TextValue> {
+                input!(@field self $( $text )?)
+            }
+        }
+
+        impl dalgi::input::EncodeState for $input_type {
+            fn encode_bits(&self, bits: &mut dalgi::input::BitWriter) {
+                $(
+                    bits.write_button(&self.button.$button);
+                )*
+                $(
+                    bits.write_bit(self.signal.$signal);
+                )*
+                $(
+                    $(
+                        bits.write_f32(self.axis.$axis.value);
+                    )*
+                )?
+                $(
+                    $(
+                        bits.write_f32(self.vector.$vector.value.0);
+                        bits.write_f32(self.vector.$vector.value.1);
+                    )*
+                )?
+            }
+
+            fn decode_bits(&mut self, bits: &mut dalgi::input::BitReader)
+                           -> Result<(), dalgi::input::DecodeError> {
+                $(
+                    bits.read_button(&mut self.button.$button)?;
+                )*
+                $(
+                    self.signal.$signal = bits.read_bit()?;
+                )*
+                $(
+                    $(
+                        self.axis.$axis.value = bits.read_f32()?;
+                    )*
+                )?
+                $(
+                    $(
+                        self.vector.$vector.value = (bits.read_f32()?, bits.read_f32()?);
+                    )*
+                )?
+                Ok(())
+            }
+
+            fn encode_delta_bits(&self, previous: &Self, bits: &mut dalgi::input::BitWriter) {
+                $(
+                    bits.write_button_delta(&self.button.$button, &previous.button.$button);
+                )*
+                $(
+                    bits.write_bit(self.signal.$signal);
+                )*
+                $(
+                    $(
+                        bits.write_f32_delta(self.axis.$axis.value, previous.axis.$axis.value);
+                    )*
+                )?
+                $(
+                    $(
+                        bits.write_f32_delta(self.vector.$vector.value.0, previous.vector.$vector.value.0);
+                        bits.write_f32_delta(self.vector.$vector.value.1, previous.vector.$vector.value.1);
+                    )*
+                )?
+            }
+
+            fn decode_delta_bits(&mut self, previous: &Self, bits: &mut dalgi::input::BitReader)
+                                 -> Result<(), dalgi::input::DecodeError> {
+                $(
+                    bits.read_button_delta(&mut self.button.$button, &previous.button.$button)?;
+                )*
+                $(
+                    self.signal.$signal = bits.read_bit()?;
+                )*
+                $(
+                    $(
+                        self.axis.$axis.value = bits.read_f32_delta(previous.axis.$axis.value)?;
+                    )*
+                )?
+                $(
+                    $(
+                        self.vector.$vector.value = (bits.read_f32_delta(previous.vector.$vector.value.0)?,
+                                                     bits.read_f32_delta(previous.vector.$vector.value.1)?);
+                    )*
+                )?
+                Ok(())
+            }
         }
     }
 }
@@ -1,22 +1,49 @@
 //! Functionality to map from events to input state changes.
 
 use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use super::key::Key;
+use super::mouse::{MouseButton, WheelDirection};
+use super::gamepad::{GamepadButton, GamepadAxis};
 use super::signal::Signal;
 use super::description::{InputDesc, KeyDesc};
-use super::change::{DescribeInputChanges, InputChange};
-use super::state::{InputState, InputIndex};
+use super::change::{DescribeInputChanges, ButtonChange, InputChange};
+use super::state::{InputState, InputIndex, NoInputs, AdvanceFrame, ButtonValue};
+use super::sequence::{Sequence, Window};
+use super::composite::{BothHeld, VirtualAxis, VirtualVector, Chord};
+use super::capture::{Capture, Captured};
 
 /// A description of events that can change the state of a button-type input.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum ButtonUpdateSource {
+    /// A keyboard key.
     Key(KeyDesc),
+    /// A mouse button.
+    MouseButton(MouseButton),
+    /// A notch of the mouse wheel in a direction.
+    Wheel(WheelDirection),
+    /// A button on the gamepad with the given index.
+    GamepadButton(u8, GamepadButton),
+    /// Several non-modifier keys held together.
+    ///
+    /// The button is pressed when the last key goes down, and released when
+    /// any of the keys goes up.
+    Chord(Vec<KeyDesc>),
 }
 
 impl ButtonUpdateSource {
-    fn try_from(desc: InputDesc) -> Option<ButtonUpdateSource> {
+    /// Returns the button source described by `desc`, if it can drive a
+    /// button.
+    pub fn try_from(desc: InputDesc) -> Option<ButtonUpdateSource> {
         match desc {
             InputDesc::Key(keydesc) => Some(ButtonUpdateSource::Key(keydesc)),
-            InputDesc::Signal(_) => None,
+            InputDesc::MouseButton(button) => Some(ButtonUpdateSource::MouseButton(button)),
+            InputDesc::Wheel(direction) => Some(ButtonUpdateSource::Wheel(direction)),
+            InputDesc::GamepadButton(index, button) => {
+                Some(ButtonUpdateSource::GamepadButton(index, button))
+            }
+            InputDesc::Chord(keys) => Some(ButtonUpdateSource::Chord(keys)),
+            InputDesc::Signal(_) | InputDesc::GamepadAxis(..) | InputDesc::Text => None,
         }
     }
 }
@@ -33,18 +60,68 @@ impl From<Key> for ButtonUpdateSource {
     }
 }
 
-impl Into<InputDesc> for ButtonUpdateSource {
-    fn into(self: ButtonUpdateSource) -> InputDesc {
-        match self {
+impl From<MouseButton> for ButtonUpdateSource {
+    fn from(button: MouseButton) -> ButtonUpdateSource {
+        ButtonUpdateSource::MouseButton(button)
+    }
+}
+
+impl From<WheelDirection> for ButtonUpdateSource {
+    fn from(direction: WheelDirection) -> ButtonUpdateSource {
+        ButtonUpdateSource::Wheel(direction)
+    }
+}
+
+impl From<GamepadButton> for ButtonUpdateSource {
+    fn from(button: GamepadButton) -> ButtonUpdateSource {
+        ButtonUpdateSource::GamepadButton(0, button)
+    }
+}
+
+impl From<ButtonUpdateSource> for InputDesc {
+    fn from(source: ButtonUpdateSource) -> InputDesc {
+        match source {
             ButtonUpdateSource::Key(desc) => InputDesc::Key(desc),
+            ButtonUpdateSource::MouseButton(button) => InputDesc::MouseButton(button),
+            ButtonUpdateSource::Wheel(direction) => InputDesc::Wheel(direction),
+            ButtonUpdateSource::GamepadButton(index, button) => {
+                InputDesc::GamepadButton(index, button)
+            }
+            ButtonUpdateSource::Chord(keys) => InputDesc::Chord(keys),
         }
     }
 }
 
 /// A description of events that can change the state of a signal-type input.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum SignalUpdateSource {
+    /// A signal from the OS.
     Signal(Signal),
+    /// A keyboard key being pressed.
     Key(KeyDesc),
+    /// A mouse button being pressed.
+    MouseButton(MouseButton),
+    /// A notch of the mouse wheel in a direction.
+    Wheel(WheelDirection),
+    /// A button on the gamepad with the given index being pressed.
+    GamepadButton(u8, GamepadButton),
+}
+
+impl SignalUpdateSource {
+    /// Returns the signal source described by `desc`, if it can send a
+    /// signal.
+    pub fn try_from(desc: InputDesc) -> Option<SignalUpdateSource> {
+        match desc {
+            InputDesc::Signal(signal) => Some(SignalUpdateSource::Signal(signal)),
+            InputDesc::Key(keydesc) => Some(SignalUpdateSource::Key(keydesc)),
+            InputDesc::MouseButton(button) => Some(SignalUpdateSource::MouseButton(button)),
+            InputDesc::Wheel(direction) => Some(SignalUpdateSource::Wheel(direction)),
+            InputDesc::GamepadButton(index, button) => {
+                Some(SignalUpdateSource::GamepadButton(index, button))
+            }
+            InputDesc::GamepadAxis(..) | InputDesc::Chord(_) | InputDesc::Text => None,
+        }
+    }
 }
 
 impl From<KeyDesc> for SignalUpdateSource {
@@ -65,49 +142,540 @@ impl From<Signal> for SignalUpdateSource {
     }
 }
 
-impl Into<InputDesc> for SignalUpdateSource {
-    fn into(self: SignalUpdateSource) -> InputDesc {
-        match self {
+impl From<MouseButton> for SignalUpdateSource {
+    fn from(button: MouseButton) -> SignalUpdateSource {
+        SignalUpdateSource::MouseButton(button)
+    }
+}
+
+impl From<WheelDirection> for SignalUpdateSource {
+    fn from(direction: WheelDirection) -> SignalUpdateSource {
+        SignalUpdateSource::Wheel(direction)
+    }
+}
+
+impl From<GamepadButton> for SignalUpdateSource {
+    fn from(button: GamepadButton) -> SignalUpdateSource {
+        SignalUpdateSource::GamepadButton(0, button)
+    }
+}
+
+impl From<SignalUpdateSource> for InputDesc {
+    fn from(source: SignalUpdateSource) -> InputDesc {
+        match source {
             SignalUpdateSource::Key(desc) => InputDesc::Key(desc),
             SignalUpdateSource::Signal(note) => InputDesc::Signal(note),
+            SignalUpdateSource::MouseButton(button) => InputDesc::MouseButton(button),
+            SignalUpdateSource::Wheel(direction) => InputDesc::Wheel(direction),
+            SignalUpdateSource::GamepadButton(index, button) => {
+                InputDesc::GamepadButton(index, button)
+            }
+        }
+    }
+}
+
+/// A description of events that can change the state of an axis-type input.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AxisUpdateSource {
+    /// An analog axis on the gamepad with the given index.
+    GamepadAxis(u8, GamepadAxis),
+}
+
+impl AxisUpdateSource {
+    /// Returns the axis source described by `desc`, if it can drive an axis.
+    pub fn try_from(desc: InputDesc) -> Option<AxisUpdateSource> {
+        match desc {
+            InputDesc::GamepadAxis(index, axis) => Some(AxisUpdateSource::GamepadAxis(index, axis)),
+            _ => None,
+        }
+    }
+}
+
+impl From<GamepadAxis> for AxisUpdateSource {
+    fn from(axis: GamepadAxis) -> AxisUpdateSource {
+        AxisUpdateSource::GamepadAxis(0, axis)
+    }
+}
+
+impl From<AxisUpdateSource> for InputDesc {
+    fn from(source: AxisUpdateSource) -> InputDesc {
+        match source {
+            AxisUpdateSource::GamepadAxis(index, axis) => InputDesc::GamepadAxis(index, axis),
+        }
+    }
+}
+
+/// Identifies an action of any category in an input map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionId<BI, NI, AI = NoInputs, VI = NoInputs> {
+    /// A button action.
+    Button(BI),
+    /// A signal action.
+    Signal(NI),
+    /// An axis action.
+    Axis(AI),
+    /// A vector action.
+    Vector(VI),
+}
+
+/// A source that is bound to several actions that aren't allowed to share it
+/// (see `InputMap::conflicts`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict<BI, NI, AI = NoInputs, VI = NoInputs> {
+    /// The source.
+    pub source: InputDesc,
+    /// Every action that the source is bound to.
+    pub actions: Vec<ActionId<BI, NI, AI, VI>>,
+}
+
+/// Something that maps input events to changes of an input state: an
+/// `InputMap` or a `ContextStack`.
+pub trait MapEvents<S> {
+    /// Applies the changes described by the event to the input state
+    /// (see `InputMap::apply`).
+    fn apply_event<E: DescribeInputChanges>(&mut self, event: &E, state: &mut S);
+}
+
+/// Removes the binding of a source to an action, and returns whether it
+/// existed.
+fn remove_binding<I: InputIndex>(bindings: &mut HashMap<InputDesc, Vec<I>>,
+                                 desc: &InputDesc,
+                                 action: I)
+                                 -> bool {
+    let (removed, now_unbound) = match bindings.get_mut(desc) {
+        Some(actions) => {
+            let len = actions.len();
+            actions.retain(|&id| id != action);
+            (actions.len() != len, actions.is_empty())
+        }
+        None => (false, false),
+    };
+    if now_unbound {
+        bindings.remove(desc);
+    }
+    removed
+}
+
+/// Removes every binding to the action.
+fn clear_bindings<I: InputIndex>(bindings: &mut HashMap<InputDesc, Vec<I>>, action: I) {
+    for actions in bindings.values_mut() {
+        actions.retain(|&id| id != action);
+    }
+    bindings.retain(|_, actions| !actions.is_empty());
+}
+
+/// Returns whether the group of the action, if any, is enabled.
+fn is_action_enabled<BI, NI, AI, VI>(groups: &HashMap<ActionId<BI, NI, AI, VI>, String>,
+                                     disabled_groups: &HashSet<String>,
+                                     action: ActionId<BI, NI, AI, VI>)
+                                     -> bool
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    groups.get(&action).is_none_or(|group| !disabled_groups.contains(group))
+}
+
+/// Returns whether a single press of `source`, with its modifiers held, can
+/// trigger a binding to `bound` (see `InputMap::conflicts`).
+fn triggered_together(bound: &InputDesc, source: &InputDesc) -> bool {
+    match (bound, source) {
+        (InputDesc::Key(bound), InputDesc::Key(source)) => {
+            bound.key == source.key &&
+            if source.mods.is_empty() {
+                bound.mods.is_empty()
+            } else {
+                bound.mods.overlaps(source.mods)
+            }
         }
+        _ => bound == source,
+    }
+}
+
+/// Applies a deadzone to an axis value, scaling the rest of the range so
+/// that the value still reaches -1 and 1.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+    }
+}
+
+/// Returns what a disabled action should see of the change: releases, and
+/// analog axes at rest.
+fn neutral_change(change: &InputChange) -> Option<InputChange> {
+    match *change {
+        InputChange::GamepadAxis(index, axis, _) => Some(InputChange::GamepadAxis(index, axis, 0.0)),
+        _ if change.button_change() == Some(ButtonChange::Released) => Some(change.clone()),
+        _ => None,
     }
 }
 
+/// When a change is applied.
+#[derive(Debug, Clone, Copy)]
+struct Moment {
+    /// The frame of the map.
+    frame: u64,
+    /// The time of the map (see `InputMap::now`).
+    now: Instant,
+    /// The number of the event that the change is part of.
+    event: u64,
+}
+
+/// Bookkeeping for a button action that lasts across frames.
+#[derive(Debug, Clone, Default)]
+struct ButtonTracker {
+    /// The maximum gap between two presses for a double tap.
+    double_tap: Option<Window>,
+    /// The frame and time of the last press that wasn't part of a double tap.
+    last_press: Option<(u64, Instant)>,
+    /// The event of the last press, so that the sources of one physical
+    /// press (ie: `a` and `[a]`) count as a single press for double taps.
+    last_press_event: Option<u64>,
+    /// For how many frames presses are buffered.
+    buffer: Option<u32>,
+    /// The bound sources that are currently held down.
+    held_sources: Vec<InputDesc>,
+}
+
+impl ButtonTracker {
+    /// Marks the source as held, and presses the button if no other source
+    /// was holding it.
+    ///
+    /// `held_elsewhere` is whether the sources of another map (ie: another
+    /// context) hold the button.
+    fn press_source(&mut self,
+                    source: &InputDesc,
+                    button: &mut ButtonValue,
+                    at: Moment,
+                    held_elsewhere: bool) {
+        if self.held_sources.contains(source) {
+            return;
+        }
+        self.held_sources.push(source.clone());
+        if self.held_sources.len() == 1 && !held_elsewhere {
+            self.press(button, at);
+        }
+    }
+
+    /// Marks the source as no longer held, and releases the button if no
+    /// other source is holding it.
+    fn release_source(&mut self,
+                      source: &InputDesc,
+                      button: &mut ButtonValue,
+                      now: Instant,
+                      held_elsewhere: bool) {
+        self.held_sources.retain(|held| held != source);
+        if self.held_sources.is_empty() && !held_elsewhere && button.held {
+            button.release(Some(now));
+        }
+    }
+
+    /// Taps the button, unless a source is holding it.
+    fn tap_source(&mut self, button: &mut ButtonValue, at: Moment, held_elsewhere: bool) {
+        if self.held_sources.is_empty() && !held_elsewhere {
+            self.tap(button, at);
+        }
+    }
+
+    /// Marks the button as pressed, and checks for a double tap.
+    fn press(&mut self, button: &mut ButtonValue, at: Moment) {
+        button.press(Some(at.now));
+        self.check_double_tap(button, at);
+        if let Some(frames) = self.buffer {
+            button.buffer(frames);
+        }
+    }
+
+    /// Marks the button as pressed and released, and checks for a double tap.
+    fn tap(&mut self, button: &mut ButtonValue, at: Moment) {
+        button.tap();
+        self.check_double_tap(button, at);
+        if let Some(frames) = self.buffer {
+            button.buffer(frames);
+        }
+    }
+
+    /// Sets `double_pressed` if this press came quickly after the last one.
+    ///
+    /// Presses in the same event as the last one are part of the same
+    /// physical press, and don't count.
+    fn check_double_tap(&mut self, button: &mut ButtonValue, at: Moment) {
+        if self.last_press_event == Some(at.event) {
+            return;
+        }
+        self.last_press_event = Some(at.event);
+        if let Some(window) = self.double_tap {
+            match self.last_press {
+                Some((last_frame, last_time)) if window.contains(at.frame - last_frame,
+                                                                 at.now.duration_since(last_time)) => {
+                    button.double_pressed = true;
+                    self.last_press = None;
+                }
+                _ => self.last_press = Some((at.frame, at.now)),
+            }
+        }
+    }
+}
 
 /// Associates abstract input descriptions with game actions, and maps input
 /// events to updates to a representation of the game's full action state.
+///
+/// The axis and vector ids default to `NoInputs`, for input states without
+/// axes or vectors.
+///
+/// Some bindings (ie: sequences) depend on frame timing. For these, call
+/// `advance_frame` on the map whenever it is called on the input state.
+/// Time-based windows and hold times read the clock when an event is
+/// applied, unless the map is given a fixed frame time
+/// (see `set_frame_time`).
+///
+/// Besides its bindings, the map tracks the input it has seen: which
+/// sources hold each button, the keys held towards chords and virtual axes,
+/// sequence progress, double taps and the values of axis sources. This is
+/// why `apply` takes `&mut self`. A clone of the map carries this tracking
+/// along; to copy only the bindings, use `add_unbound_buttons_from` and
+/// `add_unbound_signals_from` on a new map.
 #[derive(Debug, Clone)]
-pub struct InputMap<BI: InputIndex, NI: InputIndex> {
+pub struct InputMap<BI, NI, AI = NoInputs, VI = NoInputs>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
     buttons: HashMap<InputDesc, Vec<BI>>,
     signals: HashMap<InputDesc, Vec<NI>>,
+    axes: HashMap<InputDesc, Vec<AI>>,
+    virtual_axes: Vec<(AI, VirtualAxis)>,
+    vectors: Vec<(VI, VirtualVector)>,
+    chords: Vec<(InputDesc, Chord)>,
+    button_sequences: Vec<(BI, Sequence)>,
+    signal_sequences: Vec<(NI, Sequence)>,
+    trackers: HashMap<BI, ButtonTracker>,
+    groups: HashMap<ActionId<BI, NI, AI, VI>, String>,
+    shared_groups: Vec<(String, String)>,
+    disabled_groups: HashSet<String>,
+    /// Whether the map is active, or treats every action as disabled (ie:
+    /// in a disabled context).
+    active: bool,
+    capture: Option<Capture>,
+    captured: Option<Captured>,
+    /// Actions whose bindings changed since the last event, and whose state
+    /// must be updated (see `refresh`).
+    pending: Vec<ActionId<BI, NI, AI, VI>>,
+    deadzones: HashMap<InputDesc, f32>,
+    axis_values: HashMap<InputDesc, f32>,
+    wheel_progress: HashMap<WheelDirection, f32>,
+    frame: u64,
+    event: u64,
+    frame_time: Option<Duration>,
+    epoch: Instant,
+    /// How far the frame clock is past `epoch`.
+    elapsed: Duration,
 }
 
-impl<BI: InputIndex, NI: InputIndex> InputMap<BI, NI> {
+impl<BI, NI, AI, VI> InputMap<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
     /// Creates a new input map.
-    pub fn new() -> InputMap<BI, NI> {
+    pub fn new() -> InputMap<BI, NI, AI, VI> {
         InputMap {
             buttons: HashMap::new(),
             signals: HashMap::new(),
+            axes: HashMap::new(),
+            virtual_axes: Vec::new(),
+            vectors: Vec::new(),
+            chords: Vec::new(),
+            button_sequences: Vec::new(),
+            signal_sequences: Vec::new(),
+            trackers: HashMap::new(),
+            groups: HashMap::new(),
+            shared_groups: Vec::new(),
+            disabled_groups: HashSet::new(),
+            active: true,
+            capture: None,
+            captured: None,
+            pending: Vec::new(),
+            deadzones: HashMap::new(),
+            axis_values: HashMap::new(),
+            wheel_progress: HashMap::new(),
+            frame: 0,
+            event: 0,
+            frame_time: None,
+            epoch: Instant::now(),
+            elapsed: Duration::from_secs(0),
         }
     }
 
     /// Adds a mapping from a button input source to a button action.
+    ///
+    /// # Panics
+    /// Panics if the source is a chord of fewer than two keys, or with a
+    /// modifier key (see `InputDesc::is_valid_chord`).
     pub fn add_button<D: Into<ButtonUpdateSource>>(&mut self, action: BI, desc: D) {
-        self.buttons.entry(desc.into().into()).or_insert_with(Vec::new).push(action);
+        let desc: InputDesc = desc.into().into();
+        if let InputDesc::Chord(ref keys) = desc {
+            assert!(InputDesc::is_valid_chord(keys),
+                    "invalid chord (needs two or more non-modifier keys): {}",
+                    desc);
+            if !self.chords.iter().any(|(chord, _)| *chord == desc) {
+                self.chords.push((desc.clone(), Chord::new(keys)));
+            }
+        }
+        self.buttons.entry(desc).or_default().push(action);
     }
 
     /// Adds a mapping from a signal input source to a signal action.
     pub fn add_signal<D: Into<SignalUpdateSource>>(&mut self, action: NI, desc: D) {
-        self.signals.entry(desc.into().into()).or_insert_with(Vec::new).push(action);
+        self.signals.entry(desc.into().into()).or_default().push(action);
+    }
+
+    /// Adds a mapping from an axis input source to an axis action.
+    ///
+    /// If several sources drive the same action, their values are added and
+    /// clamped to the -1..1 range, together with those of its virtual axes.
+    pub fn add_axis<D: Into<AxisUpdateSource>>(&mut self, action: AI, desc: D) {
+        self.axes.entry(desc.into().into()).or_default().push(action);
+    }
+
+    /// Sets the deadzone of an axis-type source, which applies to every
+    /// binding that uses it (axes, vectors and sequences).
+    ///
+    /// Values within `deadzone` of 0 are read as 0, and the rest of the range
+    /// is scaled so that the source still reaches -1 and 1.
+    pub fn set_deadzone<D: Into<AxisUpdateSource>>(&mut self, source: D, deadzone: f32) {
+        self.deadzones.insert(source.into().into(), deadzone.clamp(0.0, 0.99));
+    }
+
+    /// Removes the deadzone of an axis-type source.
+    pub fn clear_deadzone<D: Into<AxisUpdateSource>>(&mut self, source: D) {
+        self.deadzones.remove(&source.into().into());
+    }
+
+    /// Returns the deadzone of an axis-type source, if it has one.
+    pub fn deadzone<D: Into<AxisUpdateSource>>(&self, source: D) -> Option<f32> {
+        self.deadzones.get(&source.into().into()).cloned()
+    }
+
+    /// Enables double-tap detection for a button action.
+    ///
+    /// When the action is pressed again within `gap` of the previous press,
+    /// its `double_pressed` flag is set. A third press starts a new double tap.
+    pub fn set_double_tap(&mut self, action: BI, gap: Window) {
+        self.trackers.entry(action).or_default().double_tap = Some(gap);
+    }
+
+    /// Disables double-tap detection for a button action.
+    pub fn clear_double_tap(&mut self, action: BI) {
+        if let Some(tracker) = self.trackers.get_mut(&action) {
+            tracker.double_tap = None;
+            tracker.last_press = None;
+        }
+    }
+
+    /// Enables input buffering for a button action.
+    ///
+    /// A press stays buffered for `frames` frames after the frame it happened
+    /// in, until it is consumed (see `ButtonValue::buffered` and
+    /// `ButtonValue::consume`).
+    pub fn set_buffer(&mut self, action: BI, frames: u32) {
+        self.trackers.entry(action).or_default().buffer = Some(frames);
     }
 
-    /// Returns the ids of the buttons bound by this map.
+    /// Disables input buffering for a button action.
+    pub fn clear_buffer(&mut self, action: BI) {
+        if let Some(tracker) = self.trackers.get_mut(&action) {
+            tracker.buffer = None;
+        }
+    }
+
+    /// Adds a sequence which presses (and immediately releases) a button
+    /// action when completed.
+    pub fn add_button_sequence(&mut self, action: BI, sequence: Sequence) {
+        self.button_sequences.push((action, sequence));
+    }
+
+    /// Adds a sequence which sends a signal action when completed.
+    pub fn add_signal_sequence(&mut self, action: NI, sequence: Sequence) {
+        self.signal_sequences.push((action, sequence));
+    }
+
+    /// Adds a virtual axis, which drives an axis action with a negative and a
+    /// positive button-type source (ie: `Key::Left` and `Key::Right`).
+    ///
+    /// The axis is -1 while the negative source is held, 1 while the positive
+    /// source is held, and follows `policy` while both are held.
+    /// If several virtual axes drive the same action, their values are added
+    /// and clamped to the -1..1 range, so `[a]`/`[d]` can be bound next to the
+    /// arrow keys.
+    pub fn add_virtual_axis<N, P>(&mut self, action: AI, negative: N, positive: P, policy: BothHeld)
+        where N: Into<ButtonUpdateSource>,
+              P: Into<ButtonUpdateSource>
+    {
+        let axis = VirtualAxis::new(negative.into().into(), positive.into().into(), policy);
+        self.virtual_axes.push((action, axis));
+    }
+
+    /// Adds a vector binding driven by four directional button-type sources
+    /// (ie: `[w]`, `[s]`, `[a]` and `[d]`).
+    ///
+    /// If `normalize` is set, diagonals have a length of 1, so that moving
+    /// diagonally isn't faster than moving straight.
+    /// If several bindings drive the same action, their values are added, so
+    /// the keyboard and a stick can feed the same action. If any of them is
+    /// normalized, the length of the sum is clamped to 1; otherwise each
+    /// component is clamped to the -1..1 range.
+    pub fn add_vector_buttons<U, D, L, R>(&mut self,
+                                          action: VI,
+                                          up: U,
+                                          down: D,
+                                          left: L,
+                                          right: R,
+                                          normalize: bool)
+        where U: Into<ButtonUpdateSource>,
+              D: Into<ButtonUpdateSource>,
+              L: Into<ButtonUpdateSource>,
+              R: Into<ButtonUpdateSource>
+    {
+        let vector = VirtualVector::from_buttons(up.into().into(),
+                                                 down.into().into(),
+                                                 left.into().into(),
+                                                 right.into().into(),
+                                                 normalize);
+        self.vectors.push((action, vector));
+    }
+
+    /// Adds a vector binding driven by a horizontal and a vertical axis-type
+    /// source (ie: the left stick of a gamepad).
+    ///
+    /// If `normalize` is set, the length of the vector is clamped to 1.
+    /// See `add_vector_buttons` for how several bindings are combined.
+    pub fn add_vector_axes<X, Y>(&mut self, action: VI, x: X, y: Y, normalize: bool)
+        where X: Into<AxisUpdateSource>,
+              Y: Into<AxisUpdateSource>
+    {
+        let vector = VirtualVector::from_axes(x.into().into(), y.into().into(), normalize);
+        self.vectors.push((action, vector));
+    }
+
+    /// Returns the ids of the buttons bound by this map, to sources or to
+    /// sequences.
     pub fn bound_buttons(&self) -> HashSet<BI> {
-        self.buttons.values().flat_map(|v| v).map(|id| *id).collect()
+        self.buttons
+            .values()
+            .flatten()
+            .chain(self.button_sequences.iter().map(|(id, _)| id)).copied()
+            .collect()
     }
 
-    /// Returns the sources
+    /// Returns the sources bound to the given button action.
+    ///
+    /// Sequences are listed separately (see `button_sequences`).
     pub fn button_sources(&self, action: BI) -> Vec<ButtonUpdateSource> {
         self.buttons
             .iter()
@@ -116,69 +684,884 @@ impl<BI: InputIndex, NI: InputIndex> InputMap<BI, NI> {
             .collect()
     }
 
+    /// Returns the sequences bound to the given button action.
+    pub fn button_sequences(&self, action: BI) -> Vec<&Sequence> {
+        self.button_sequences
+            .iter()
+            .filter(|&&(id, _)| id == action)
+            .map(|(_, sequence)| sequence)
+            .collect()
+    }
+
+    /// Returns the ids of the signals bound by this map, to sources or to
+    /// sequences.
+    pub fn bound_signals(&self) -> HashSet<NI> {
+        self.signals
+            .values()
+            .flatten()
+            .chain(self.signal_sequences.iter().map(|(id, _)| id)).copied()
+            .collect()
+    }
+
+    /// Returns the sources bound to the given signal action.
+    ///
+    /// Sequences are listed separately (see `signal_sequences`).
+    pub fn signal_sources(&self, action: NI) -> Vec<SignalUpdateSource> {
+        self.signals
+            .iter()
+            .filter(|&(_, ids)| ids.contains(&action))
+            .map(|(d, _)| SignalUpdateSource::try_from(d.clone()).unwrap())
+            .collect()
+    }
+
+    /// Returns the sequences bound to the given signal action.
+    pub fn signal_sequences(&self, action: NI) -> Vec<&Sequence> {
+        self.signal_sequences
+            .iter()
+            .filter(|&&(id, _)| id == action)
+            .map(|(_, sequence)| sequence)
+            .collect()
+    }
+
+    /// Returns the ids of the axes bound by this map.
+    pub fn bound_axes(&self) -> HashSet<AI> {
+        self.axes
+            .values()
+            .flatten()
+            .chain(self.virtual_axes.iter().map(|(id, _)| id)).copied()
+            .collect()
+    }
+
+    /// Returns the sources bound to the given axis action.
+    pub fn axis_sources(&self, action: AI) -> Vec<AxisUpdateSource> {
+        self.axes
+            .iter()
+            .filter(|&(_, ids)| ids.contains(&action))
+            .map(|(d, _)| AxisUpdateSource::try_from(d.clone()).unwrap())
+            .collect()
+    }
+
+    /// Returns the ids of the vectors bound by this map.
+    pub fn bound_vectors(&self) -> HashSet<VI> {
+        self.vectors.iter().map(|&(id, _)| id).collect()
+    }
+
+    /// Removes a mapping from a button input source to a button action.
+    ///
+    /// If the source was the last one holding the button, the button is
+    /// released on the next refresh (see `refresh`). The same goes for the
+    /// other ways of removing bindings, and axes and vectors that lose
+    /// bindings take the value of the remaining ones.
+    ///
+    /// Returns whether the mapping existed.
+    pub fn remove_button<D: Into<ButtonUpdateSource>>(&mut self, action: BI, desc: D) -> bool {
+        let desc = desc.into().into();
+        let removed = remove_binding(&mut self.buttons, &desc, action);
+        let mut released = false;
+        if let Some(tracker) = self.trackers.get_mut(&action) {
+            let was_held = !tracker.held_sources.is_empty();
+            tracker.held_sources.retain(|held| *held != desc);
+            released = was_held && tracker.held_sources.is_empty();
+        }
+        if released {
+            self.mark_pending(ActionId::Button(action));
+        }
+        self.remove_unbound_chords();
+        removed
+    }
+
+    /// Removes a mapping from a signal input source to a signal action.
+    ///
+    /// Returns whether the mapping existed.
+    pub fn remove_signal<D: Into<SignalUpdateSource>>(&mut self, action: NI, desc: D) -> bool {
+        remove_binding(&mut self.signals, &desc.into().into(), action)
+    }
+
+    /// Removes a mapping from an axis input source to an axis action.
+    ///
+    /// Returns whether the mapping existed.
+    pub fn remove_axis<D: Into<AxisUpdateSource>>(&mut self, action: AI, desc: D) -> bool {
+        let removed = remove_binding(&mut self.axes, &desc.into().into(), action);
+        self.mark_pending(ActionId::Axis(action));
+        removed
+    }
+
+    /// Removes every source and sequence bound to the button action.
+    ///
+    /// The double-tap and buffer settings of the action are kept.
+    pub fn clear_button(&mut self, action: BI) {
+        clear_bindings(&mut self.buttons, action);
+        self.button_sequences.retain(|&(id, _)| id != action);
+        self.forget_held_sources(action);
+        self.remove_unbound_chords();
+    }
+
+    /// Removes every source and sequence bound to the signal action.
+    pub fn clear_signal(&mut self, action: NI) {
+        clear_bindings(&mut self.signals, action);
+        self.signal_sequences.retain(|&(id, _)| id != action);
+    }
+
+    /// Removes every source and virtual axis bound to the axis action.
+    pub fn clear_axis(&mut self, action: AI) {
+        clear_bindings(&mut self.axes, action);
+        self.virtual_axes.retain(|&(id, _)| id != action);
+        self.mark_pending(ActionId::Axis(action));
+    }
+
+    /// Removes every binding of the vector action.
+    pub fn clear_vector(&mut self, action: VI) {
+        self.vectors.retain(|&(id, _)| id != action);
+        self.mark_pending(ActionId::Vector(action));
+    }
+
+    /// Replaces the sources bound to the button action.
+    ///
+    /// Sequences bound to the action are kept.
+    pub fn replace_buttons<I, D>(&mut self, action: BI, descs: I)
+        where I: IntoIterator<Item = D>,
+              D: Into<ButtonUpdateSource>
+    {
+        clear_bindings(&mut self.buttons, action);
+        self.forget_held_sources(action);
+        for desc in descs {
+            self.add_button(action, desc);
+        }
+        self.remove_unbound_chords();
+    }
+
+    /// Replaces the sources bound to the signal action.
+    ///
+    /// Sequences bound to the action are kept.
+    pub fn replace_signals<I, D>(&mut self, action: NI, descs: I)
+        where I: IntoIterator<Item = D>,
+              D: Into<SignalUpdateSource>
+    {
+        clear_bindings(&mut self.signals, action);
+        for desc in descs {
+            self.add_signal(action, desc);
+        }
+    }
+
+    /// Replaces the sources bound to the axis action.
+    ///
+    /// Virtual axes bound to the action are kept.
+    pub fn replace_axes<I, D>(&mut self, action: AI, descs: I)
+        where I: IntoIterator<Item = D>,
+              D: Into<AxisUpdateSource>
+    {
+        clear_bindings(&mut self.axes, action);
+        for desc in descs {
+            self.add_axis(action, desc);
+        }
+        self.mark_pending(ActionId::Axis(action));
+    }
+
+    /// Removes every binding of every action.
+    ///
+    /// Per-action settings (double taps, buffers and groups) are kept.
+    pub fn clear(&mut self) {
+        for axis in self.bound_axes() {
+            self.mark_pending(ActionId::Axis(axis));
+        }
+        for vector in self.bound_vectors() {
+            self.mark_pending(ActionId::Vector(vector));
+        }
+        for button in self.held_buttons() {
+            self.forget_held_sources(button);
+        }
+        self.buttons.clear();
+        self.signals.clear();
+        self.axes.clear();
+        self.virtual_axes.clear();
+        self.vectors.clear();
+        self.chords.clear();
+        self.button_sequences.clear();
+        self.signal_sequences.clear();
+    }
+
+    /// Forgets which sources hold the button action, and releases it on the
+    /// next refresh if any did.
+    fn forget_held_sources(&mut self, action: BI) {
+        let was_held = match self.trackers.get_mut(&action) {
+            Some(tracker) => {
+                let was_held = !tracker.held_sources.is_empty();
+                tracker.held_sources.clear();
+                was_held
+            }
+            None => false,
+        };
+        if was_held {
+            self.mark_pending(ActionId::Button(action));
+        }
+    }
+
+    /// Returns the actions whose state this map drives: the buttons held by
+    /// its sources, and its axes and vectors.
+    pub(crate) fn driven_actions(&self) -> Vec<ActionId<BI, NI, AI, VI>> {
+        let mut actions: Vec<ActionId<BI, NI, AI, VI>> = self.held_buttons()
+            .into_iter()
+            .map(ActionId::Button)
+            .collect();
+        actions.extend(self.bound_axes().into_iter().map(ActionId::Axis));
+        actions.extend(self.bound_vectors().into_iter().map(ActionId::Vector));
+        actions
+    }
+
+    /// Returns the button actions held by sources bound by this map.
+    pub(crate) fn held_buttons(&self) -> Vec<BI> {
+        self.trackers.keys().cloned().filter(|&id| self.holds(id)).collect()
+    }
+
+    /// Returns whether a source bound by this map holds the button action.
+    pub(crate) fn holds(&self, action: BI) -> bool {
+        self.trackers.get(&action).is_some_and(|tracker| !tracker.held_sources.is_empty())
+    }
+
+    /// Returns whether this map drives the axis or vector action.
+    pub(crate) fn binds_analog(&self, action: ActionId<BI, NI, AI, VI>) -> bool {
+        match action {
+            ActionId::Axis(id) => self.bound_axes().contains(&id),
+            ActionId::Vector(id) => self.bound_vectors().contains(&id),
+            ActionId::Button(_) | ActionId::Signal(_) => false,
+        }
+    }
+
+    /// Marks the state of the action as needing an update after its
+    /// bindings changed.
+    pub(crate) fn mark_pending(&mut self, action: ActionId<BI, NI, AI, VI>) {
+        if !self.pending.contains(&action) {
+            self.pending.push(action);
+        }
+    }
+
+    /// Updates the state of actions whose bindings changed since the last
+    /// event: buttons that are no longer held by any bound source are
+    /// released, and axes and vectors take the value of their remaining
+    /// bindings (or rest when their group is disabled).
+    ///
+    /// This is done at the start of `apply`, so only call this to see the
+    /// changes before the next event.
+    pub fn refresh<S>(&mut self, state: &mut S)
+        where S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
+    {
+        self.refresh_held_elsewhere(state, &[]);
+    }
+
+    /// Refreshes the state (see `refresh`), keeping the buttons in
+    /// `held_elsewhere` held since another map holds them.
+    pub(crate) fn refresh_held_elsewhere<S>(&mut self, state: &mut S, held_elsewhere: &[BI])
+        where S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
+    {
+        let now = self.now();
+        for action in std::mem::take(&mut self.pending) {
+            let enabled = self.is_enabled(action);
+            match action {
+                ActionId::Button(id) => {
+                    let held = self.holds(id) || held_elsewhere.contains(&id);
+                    let button = state.get_button(&id);
+                    if !held && button.held {
+                        button.release(Some(now));
+                    }
+                }
+                ActionId::Signal(_) => {}
+                ActionId::Axis(id) => {
+                    state.get_axis(&id).value = if enabled { self.axis_value(id) } else { 0.0 };
+                }
+                ActionId::Vector(id) => {
+                    state.get_vector(&id).value = if enabled { self.vector_value(id) } else { (0.0, 0.0) };
+                }
+            }
+        }
+    }
+
+    /// Makes the map measure time in frames of the given length, instead of
+    /// reading the clock when an event is applied.
+    ///
+    /// With a frame time, time-based windows (double taps and sequences)
+    /// and hold times only depend on the frame an event is applied in, so
+    /// that replaying the same input gives the same result (ie: with a
+    /// fixed-step game loop, or in tests). Pass `None` to read the clock
+    /// again.
+    ///
+    /// The frame clock moves forward by the frame time on every
+    /// `advance_frame`, so changing the frame time only affects later frames.
+    pub fn set_frame_time(&mut self, frame_time: Option<Duration>) {
+        self.frame_time = frame_time;
+    }
+
+    /// Returns the time the map gives to events applied now.
+    ///
+    /// This is the clock, or the start of the current frame if the map has a
+    /// frame time (see `set_frame_time`).
+    pub fn now(&self) -> Instant {
+        match self.frame_time {
+            Some(_) => self.epoch + self.elapsed,
+            None => Instant::now(),
+        }
+    }
+
+    /// Returns the combined value of the sources and virtual axes bound to
+    /// the axis action.
+    fn axis_value(&self, action: AI) -> f32 {
+        let sources: f32 = self.axes
+            .iter()
+            .filter(|&(_, ids)| ids.contains(&action))
+            .filter_map(|(desc, _)| self.axis_values.get(desc))
+            .sum();
+        let virtual_axes: f32 = self.virtual_axes
+            .iter()
+            .filter(|&&(id, _)| id == action)
+            .map(|(_, axis)| axis.value())
+            .sum();
+        (sources + virtual_axes).clamp(-1.0, 1.0)
+    }
+
+    /// Returns the combined value of the bindings of the vector action.
+    fn vector_value(&self, action: VI) -> (f32, f32) {
+        let bindings = self.vectors.iter().filter(|&&(id, _)| id == action);
+        let normalize = bindings.clone().any(|(_, vector)| vector.is_normalized());
+        let (x, y) = bindings.map(|(_, vector)| vector.value())
+            .fold((0.0, 0.0), |(x, y), (vx, vy)| (x + vx, y + vy));
+        let length = (x * x + y * y).sqrt();
+        if normalize && length > 1.0 {
+            (x / length, y / length)
+        } else if normalize {
+            (x, y)
+        } else {
+            (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0))
+        }
+    }
+
+    /// Stops tracking chords that are no longer bound to any action.
+    fn remove_unbound_chords(&mut self) {
+        let buttons = &self.buttons;
+        self.chords.retain(|(desc, _)| buttons.contains_key(desc));
+    }
+
     /// Adds all sources for button-type inputs that are bound by 'other' but
     /// not by this maps.
     /// This means that if this map doesn't map anything to 'shoot', it will get
     /// bindings from both buttons C and D from the other map.
-    pub fn add_unbound_buttons_from(&mut self, other: &InputMap<BI, NI>) {
+    pub fn add_unbound_buttons_from(&mut self, other: &InputMap<BI, NI, AI, VI>) {
         let own_buttons = self.bound_buttons();
         for button_id in other.bound_buttons() {
             if !own_buttons.contains(&button_id) {
                 for description in other.button_sources(button_id) {
                     self.add_button(button_id, description);
                 }
+                for sequence in other.button_sequences(button_id) {
+                    self.add_button_sequence(button_id, sequence.clone());
+                }
             }
         }
     }
 
+    /// Adds all sources for signal-type inputs that are bound by 'other' but
+    /// not by this map (see `add_unbound_buttons_from`).
+    pub fn add_unbound_signals_from(&mut self, other: &InputMap<BI, NI, AI, VI>) {
+        let own_signals = self.bound_signals();
+        for signal_id in other.bound_signals() {
+            if !own_signals.contains(&signal_id) {
+                for description in other.signal_sources(signal_id) {
+                    self.add_signal(signal_id, description);
+                }
+                for sequence in other.signal_sequences(signal_id) {
+                    self.add_signal_sequence(signal_id, sequence.clone());
+                }
+            }
+        }
+    }
+
+    /// Puts the action in the named group (ie: "menu" or "gameplay"),
+    /// replacing its previous group.
+    pub fn set_group(&mut self, action: ActionId<BI, NI, AI, VI>, group: &str) {
+        self.groups.insert(action, group.to_string());
+    }
+
+    /// Returns the group of the action, if it is in one.
+    pub fn group(&self, action: ActionId<BI, NI, AI, VI>) -> Option<&str> {
+        self.groups.get(&action).map(|group| &group[..])
+    }
+
+    /// Allows actions of the two groups to be bound to the same sources
+    /// without conflicts (ie: because the menu and gameplay are never active
+    /// at the same time).
+    ///
+    /// Actions in the same group never share sources, unless the group is
+    /// allowed to share with itself.
+    pub fn allow_sharing(&mut self, group: &str, other: &str) {
+        if !self.may_share_groups(group, other) {
+            self.shared_groups.push((group.to_string(), other.to_string()));
+        }
+    }
+
+    /// Enables or disables the actions of the named group (ie: to ignore
+    /// gameplay actions during a cutscene).
+    ///
+    /// Disabled actions still see releases, so that buttons held when the
+    /// group was disabled don't get stuck, and analog axes and vectors of
+    /// disabled actions are kept at rest.
+    ///
+    /// The axes and vectors of the group are updated on the next refresh
+    /// (see `refresh`).
+    pub fn set_group_enabled(&mut self, group: &str, enabled: bool) {
+        if enabled {
+            self.disabled_groups.remove(group);
+        } else {
+            self.disabled_groups.insert(group.to_string());
+        }
+        let analog: Vec<ActionId<BI, NI, AI, VI>> = self.groups
+            .iter()
+            .filter(|&(action, g)| {
+                g == group &&
+                match *action {
+                    ActionId::Axis(_) | ActionId::Vector(_) => true,
+                    ActionId::Button(_) | ActionId::Signal(_) => false,
+                }
+            })
+            .map(|(&action, _)| action)
+            .collect();
+        for action in analog {
+            self.mark_pending(action);
+        }
+    }
+
+    /// Returns whether the actions of the named group are enabled.
+    pub fn is_group_enabled(&self, group: &str) -> bool {
+        !self.disabled_groups.contains(group)
+    }
+
+    /// Returns whether the action is enabled, which it is unless its group
+    /// is disabled, or the map is in a disabled context.
+    pub fn is_enabled(&self, action: ActionId<BI, NI, AI, VI>) -> bool {
+        self.active && is_action_enabled(&self.groups, &self.disabled_groups, action)
+    }
+
+    /// Activates the map, or makes it treat every action as disabled, like a
+    /// disabled group (see `set_group_enabled`).
+    ///
+    /// The axes and vectors of the map are updated on the next refresh.
+    pub(crate) fn set_active(&mut self, active: bool) {
+        self.active = active;
+        let analog: Vec<ActionId<BI, NI, AI, VI>> = self.bound_axes()
+            .into_iter()
+            .map(ActionId::Axis)
+            .chain(self.bound_vectors().into_iter().map(ActionId::Vector))
+            .collect();
+        for action in analog {
+            self.mark_pending(action);
+        }
+    }
+
+    /// Returns whether the map is active (see `set_active`).
+    pub(crate) fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn may_share_groups(&self, group: &str, other: &str) -> bool {
+        self.shared_groups
+            .iter()
+            .any(|(a, b)| (a == group && b == other) || (a == other && b == group))
+    }
+
+    fn may_share(&self, action: ActionId<BI, NI, AI, VI>, other: ActionId<BI, NI, AI, VI>) -> bool {
+        match (self.group(action), self.group(other)) {
+            (Some(group), Some(other)) => self.may_share_groups(group, other),
+            _ => false,
+        }
+    }
+
+    /// Returns every source that triggers several actions which aren't
+    /// allowed to share it (see `allow_sharing`), sorted by source.
+    ///
+    /// A source triggers the actions of every binding that a single press of
+    /// it can trigger: backends report a key both as a key code and as a
+    /// scan code, so `a` conflicts with `[a]`, and a binding without
+    /// modifiers ignores held modifiers, so `s` conflicts with `ctrl+s`
+    /// (but `ctrl+s` doesn't conflict with `ctrl+shift+s`). Only direct
+    /// bindings are checked: the sources of virtual axes, vectors and
+    /// sequences are not, and a chord only conflicts with the same chord.
+    pub fn conflicts(&self) -> Vec<Conflict<BI, NI, AI, VI>> {
+        let mut bound = Vec::new();
+        for (desc, ids) in &self.buttons {
+            bound.extend(ids.iter().map(|id| (desc, ActionId::Button(*id))));
+        }
+        for (desc, ids) in &self.signals {
+            bound.extend(ids.iter().map(|id| (desc, ActionId::Signal(*id))));
+        }
+        for (desc, ids) in &self.axes {
+            bound.extend(ids.iter().map(|id| (desc, ActionId::Axis(*id))));
+        }
+        bound.sort_by_key(|&(desc, _)| desc.to_string());
+        let mut sources: Vec<&InputDesc> = bound.iter().map(|&(desc, _)| desc).collect();
+        sources.dedup();
+        let mut conflicts = Vec::new();
+        for source in sources {
+            let mut actions = Vec::new();
+            for &(desc, action) in &bound {
+                if triggered_together(desc, source) && !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+            let conflicting = actions.iter().enumerate().any(|(i, &action)| {
+                actions[i + 1..].iter().any(|&other| !self.may_share(action, other))
+            });
+            if conflicting {
+                conflicts.push(Conflict {
+                    source: source.clone(),
+                    actions,
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// Starts capturing the next suitable input as a new binding
+    /// (ie: for a "press a key for Jump" screen).
+    ///
+    /// While capturing, changes don't trigger any bindings, except for
+    /// releases, gamepad axes and signals (see `Capture::passes_through`).
+    /// This also holds for the rest of the event that completes the capture,
+    /// so that a backend reporting a key both as a key code and as a scan
+    /// code doesn't trigger the other one.
+    ///
+    /// The result is available through `take_captured`. Starting a new
+    /// capture discards any result that wasn't taken yet.
+    pub fn capture(&mut self, capture: Capture) {
+        self.capture = Some(capture);
+        self.captured = None;
+    }
+
+    /// Returns whether the map is waiting for an input to capture.
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Returns the capture in progress.
+    pub(crate) fn capture_in_progress(&self) -> Option<Capture> {
+        self.capture
+    }
+
+    /// Stops capturing without a result.
+    pub fn stop_capture(&mut self) {
+        self.capture = None;
+    }
+
+    /// Returns the result of the last capture, if it completed since the last
+    /// call.
+    pub fn take_captured(&mut self) -> Option<Captured> {
+        self.captured.take()
+    }
+
     /// Applies the changes described by the given event to the input state.
-    pub fn apply<E, S>(&self, event: &E, state: &mut S)
+    ///
+    /// Key changes only trigger bindings whose modifiers are satisfied by the
+    /// change (see `KeyDesc::matches`). Releases are the exception: releasing
+    /// a key releases every *held* button bound to that key, regardless of
+    /// modifiers, so that letting go of `ctrl` before `s` doesn't leave a
+    /// `ctrl+s` binding held.
+    ///
+    /// A button bound to several sources is held while any of them is held:
+    /// it is pressed when the first source goes down, and released when the
+    /// last one goes up.
+    ///
+    /// Every notch of mouse wheel movement presses and immediately releases
+    /// the buttons bound to its direction, and the movement is added to the
+    /// state's wheel value (see `InputState::get_wheel`). Fractional movement
+    /// adds up until it makes a whole notch, and is reset when the wheel
+    /// turns the other way.
+    ///
+    /// Text changes are added to the state's text value
+    /// (see `InputState::get_text`).
+    pub fn apply<E, S>(&mut self, event: &E, state: &mut S)
         where E: DescribeInputChanges,
-              S: InputState<ButtonId = BI, SignalId = NI>
+              S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
     {
-        use super::ButtonChange::*;
+        self.refresh(state);
+        self.begin_event();
+        let capture = self.capture;
         event.describe_changes(|change| {
-            let input = change.input();
-            // BUTTON MAPPING
-            for button_id in self.buttons.get(&input).into_iter().flat_map(|a| a) {
-                let mut button = state.get_button(&button_id);
-                match change {
-                    InputChange::Key(_, state) => {
-                        match state {
-                            Pressed => {
-                                button.pressed = true;
-                                button.held = true;
-                            }
-                            Released => {
-                                button.released = true;
-                                button.held = false;
-                            }
-                            Repeated => {
-                                button.repeats += 1;
-                            }
+            self.apply_change(change, state, capture, true, &[]);
+        });
+    }
+
+    /// Marks the start of a new event, so that its changes are known to be
+    /// part of the same physical input.
+    pub(crate) fn begin_event(&mut self) {
+        self.event += 1;
+    }
+
+    /// Applies a single change of an event to the input state, and returns
+    /// whether it triggered a binding of an enabled action, or was swallowed
+    /// by a capture.
+    ///
+    /// `capture` is the capture that was in progress when the event started.
+    /// Wheel movement and text are only added to the state when `accumulate`
+    /// is set, so that several maps can see the same change. Buttons in
+    /// `held_elsewhere` are held by another map, so they are neither pressed
+    /// nor released again here.
+    pub(crate) fn apply_change<S>(&mut self,
+                                  change: InputChange,
+                                  state: &mut S,
+                                  capture: Option<Capture>,
+                                  accumulate: bool,
+                                  held_elsewhere: &[BI])
+                                  -> bool
+        where S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
+    {
+        use super::ButtonChange::*;
+        if let Some(capture) = capture {
+            if self.capture.is_some() {
+                if let Some(captured) = capture.check(&change) {
+                    self.capture = None;
+                    self.captured = Some(captured);
+                }
+            }
+            if !capture.passes_through(&change) {
+                return true;
+            }
+        }
+        let input = change.input();
+        let change = match change {
+            InputChange::GamepadAxis(index, axis, value) => {
+                let value = match self.deadzones.get(&input) {
+                    Some(&deadzone) => apply_deadzone(value, deadzone),
+                    None => value,
+                };
+                self.axis_values.insert(input.clone(), value);
+                InputChange::GamepadAxis(index, axis, value)
+            }
+            change => change,
+        };
+        let now = self.now();
+        let frame = self.frame;
+        let at = Moment {
+            frame,
+            now,
+            event: self.event,
+        };
+        let mut used = false;
+        let mut taps = 1;
+        if let InputChange::Wheel(direction, notches) = change {
+            if accumulate {
+                if let Some(wheel) = state.get_wheel() {
+                    wheel.add(direction, notches);
+                }
+            }
+            self.wheel_progress.remove(&direction.opposite());
+            let progress = self.wheel_progress.entry(direction).or_insert(0.0);
+            *progress += notches;
+            let whole = progress.floor();
+            *progress -= whole;
+            taps = whole as u32;
+            if taps == 0 {
+                return false;
+            }
+        }
+        if let InputChange::Text(ref text) = change {
+            if accumulate {
+                if let Some(text_value) = state.get_text() {
+                    text_value.apply(text);
+                }
+            }
+            return false;
+        }
+        // Disabled actions only see releases, and analog values at rest.
+        let neutral = neutral_change(&change);
+        let groups = &self.groups;
+        let disabled_groups = &self.disabled_groups;
+        let active = self.active;
+        let enabled = |action| active && is_action_enabled(groups, disabled_groups, action);
+
+        // BUTTON MAPPING
+        for (desc, button_ids) in &self.buttons {
+            let button_change = change.button_change_for(desc);
+            if button_change.is_none() && !desc.matches(&input) {
+                continue;
+            }
+            for button_id in button_ids {
+                if !enabled(ActionId::Button(*button_id)) && button_change != Some(Released) {
+                    continue;
+                }
+                used = true;
+                let button = state.get_button(button_id);
+                let tracker = self.trackers.entry(*button_id).or_default();
+                let elsewhere = held_elsewhere.contains(button_id);
+                match button_change {
+                    Some(Pressed) => {
+                        tracker.press_source(desc, button, at, elsewhere);
+                    }
+                    Some(Released) => {
+                        tracker.release_source(desc, button, now, elsewhere);
+                    }
+                    Some(Repeated) => {
+                        button.repeats += 1;
+                    }
+                    None => {
+                        // Only wheel notches are bound to buttons without
+                        // being buttons themselves.
+                        // A wheel notch is a press and an instant release.
+                        for _ in 0..taps {
+                            tracker.tap_source(button, at, elsewhere);
                         }
                     }
-                    InputChange::Signal(_) => unreachable!(),
                 }
             }
+        }
 
-            // NOTIFICATION MAPPING
-            for signal_id in self.signals.get(&input).into_iter().flat_map(|a| a) {
-                let mut signal_received = state.get_signal(&signal_id);
-                match change {
-                    InputChange::Key(_, state) => {
-                        match state {
-                            Pressed => {
-                                *signal_received = true;
-                            }
-                            Released | Repeated => {}
-                        }
+        // CHORD MAPPING
+        for &mut (ref desc, ref mut chord) in &mut self.chords {
+            let chord_change = match chord.update(&change) {
+                Some(chord_change) => chord_change,
+                None => continue,
+            };
+            for button_id in self.buttons.get(desc).into_iter().flatten() {
+                if !enabled(ActionId::Button(*button_id)) && chord_change != Released {
+                    continue;
+                }
+                used = true;
+                let button = state.get_button(button_id);
+                let tracker = self.trackers.entry(*button_id).or_default();
+                let elsewhere = held_elsewhere.contains(button_id);
+                match chord_change {
+                    Pressed => {
+                        tracker.press_source(desc, button, at, elsewhere);
+                    }
+                    Released => {
+                        tracker.release_source(desc, button, now, elsewhere);
                     }
-                    InputChange::Signal(_) => {
+                    Repeated => {}
+                }
+            }
+        }
+
+        // SEQUENCE MAPPING
+        for &mut (button_id, ref mut sequence) in &mut self.button_sequences {
+            if enabled(ActionId::Button(button_id)) && sequence.update(&change, frame, now) {
+                used = true;
+                let button = state.get_button(&button_id);
+                let tracker = self.trackers.entry(button_id).or_default();
+                tracker.tap_source(button, at, held_elsewhere.contains(&button_id));
+            }
+        }
+        for &mut (signal_id, ref mut sequence) in &mut self.signal_sequences {
+            if enabled(ActionId::Signal(signal_id)) && sequence.update(&change, frame, now) {
+                used = true;
+                *state.get_signal(&signal_id) = true;
+            }
+        }
+
+        // AXIS MAPPING
+        let mut changed_axes = Vec::new();
+        if let InputChange::GamepadAxis(..) = change {
+            for axis_id in self.axes.get(&input).into_iter().flatten() {
+                used |= enabled(ActionId::Axis(*axis_id));
+                if !changed_axes.contains(axis_id) {
+                    changed_axes.push(*axis_id);
+                }
+            }
+        }
+
+        // VIRTUAL AXIS MAPPING
+        for &mut (axis_id, ref mut axis) in &mut self.virtual_axes {
+            let axis_enabled = enabled(ActionId::Axis(axis_id));
+            let axis_change = if axis_enabled { Some(&change) } else { neutral.as_ref() };
+            if axis_change.is_some_and(|c| axis.update(c)) && !changed_axes.contains(&axis_id) {
+                used |= axis_enabled;
+                changed_axes.push(axis_id);
+            }
+        }
+        for axis_id in changed_axes {
+            state.get_axis(&axis_id).value = if enabled(ActionId::Axis(axis_id)) {
+                self.axis_value(axis_id)
+            } else {
+                0.0
+            };
+        }
+
+        // VECTOR MAPPING
+        let mut changed_vectors = Vec::new();
+        for &mut (vector_id, ref mut vector) in &mut self.vectors {
+            let vector_enabled = enabled(ActionId::Vector(vector_id));
+            let vector_change = if vector_enabled { Some(&change) } else { neutral.as_ref() };
+            if vector_change.is_some_and(|c| vector.update(c)) && !changed_vectors.contains(&vector_id) {
+                used |= vector_enabled;
+                changed_vectors.push(vector_id);
+            }
+        }
+        for vector_id in changed_vectors {
+            state.get_vector(&vector_id).value = if enabled(ActionId::Vector(vector_id)) {
+                self.vector_value(vector_id)
+            } else {
+                (0.0, 0.0)
+            };
+        }
+
+        // NOTIFICATION MAPPING
+        for (desc, signal_ids) in &self.signals {
+            if !desc.matches(&input) {
+                continue;
+            }
+            for signal_id in signal_ids {
+                if !enabled(ActionId::Signal(*signal_id)) {
+                    continue;
+                }
+                let signal_received = state.get_signal(signal_id);
+                match change.button_change() {
+                    Some(Pressed) | None => {
+                        used = true;
                         *signal_received = true;
                     }
+                    Some(Released) | Some(Repeated) => {}
                 }
             }
-        });
+        }
+        used
+    }
+}
+
+impl<BI, NI, AI, VI> Default for InputMap<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    fn default() -> InputMap<BI, NI, AI, VI> {
+        InputMap::new()
+    }
+}
+
+impl<BI, NI, AI, VI, S> MapEvents<S> for InputMap<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex,
+          S: InputState<ButtonId = BI, SignalId = NI, AxisId = AI, VectorId = VI>
+{
+    fn apply_event<E: DescribeInputChanges>(&mut self, event: &E, state: &mut S) {
+        self.apply(event, state);
+    }
+}
+
+impl<BI, NI, AI, VI> AdvanceFrame for InputMap<BI, NI, AI, VI>
+    where BI: InputIndex,
+          NI: InputIndex,
+          AI: InputIndex,
+          VI: InputIndex
+{
+    fn advance_frame(&mut self) {
+        self.frame += 1;
+        if let Some(frame_time) = self.frame_time {
+            self.elapsed += frame_time;
+        }
+        let now = self.now();
+        let frame = self.frame;
+        for &mut (_, ref mut sequence) in &mut self.button_sequences {
+            sequence.expire(frame, now);
+        }
+        for &mut (_, ref mut sequence) in &mut self.signal_sequences {
+            sequence.expire(frame, now);
+        }
     }
 }
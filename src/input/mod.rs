@@ -2,11 +2,22 @@
 //! Includes an input mapping system with optional support for rsdl.
 
 mod key;
+mod mouse;
+mod gamepad;
 mod signal;
+mod text;
 mod state;
 mod description;
 mod change;
+mod composite;
 mod map;
+mod sequence;
+mod bindings;
+mod capture;
+mod context;
+mod replay;
+mod rollback;
+mod encoding;
 
 #[macro_use]
 mod macros;
@@ -15,8 +26,23 @@ mod macros;
 mod rsdl2_input;
 
 pub use self::key::Key;
+pub use self::mouse::{MouseButton, WheelDirection, WheelValue};
+pub use self::gamepad::{GamepadButton, GamepadAxis};
 pub use self::signal::Signal;
-pub use self::state::{InputState, ButtonValue, AdvanceFrame};
+pub use self::text::{TextChange, TextValue, Composition};
+pub use self::state::{InputState, ButtonValue, AxisValue, VectorValue, AdvanceFrame, NoInputs,
+                      NamedIndex};
 pub use self::description::{Keytype, Mods, KeyDesc, InputDesc};
 pub use self::change::{DescribeInputChanges, ButtonChange, InputChange};
-pub use self::map::InputMap;
+pub use self::composite::BothHeld;
+pub use self::sequence::{Sequence, Window};
+pub use self::map::{InputMap, ButtonUpdateSource, SignalUpdateSource, AxisUpdateSource, ActionId,
+                    Conflict, MapEvents};
+pub use self::encoding::{EncodeState, BitWriter, BitReader, DecodeError};
+pub use self::rollback::RollbackBuffer;
+pub use self::replay::{Recording, Replay, StateRecording};
+pub use self::context::{ContextStack, Consume};
+pub use self::capture::{Capture, CaptureDevice, Captured};
+pub use self::bindings::{Bindings, BindingsError, BindingsErrorKind};
+#[cfg(feature = "rsdl2-support")]
+pub use self::rsdl2_input::Rsdl2Events;
@@ -0,0 +1,161 @@
+//! Mouse button and wheel values.
+
+use std::borrow::Cow;
+use super::state::same_bits;
+
+/// A button on a mouse.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum MouseButton {
+    /// The left (primary) mouse button.
+    Left,
+    /// The middle mouse button (usually the wheel).
+    Middle,
+    /// The right (secondary) mouse button.
+    Right,
+    /// The first extra mouse button (usually 'back').
+    X1,
+    /// The second extra mouse button (usually 'forward').
+    X2,
+    /// Any further mouse button, identified by its number.
+    ///
+    /// Buttons are numbered from 1, so the first extra button is 6.
+    Other(u8),
+}
+
+impl MouseButton {
+    /// Returns the mouse button with the given number.
+    ///
+    /// Buttons are numbered from 1 in the order left, middle, right, x1, x2.
+    pub fn from_number(number: u8) -> MouseButton {
+        use self::MouseButton::*;
+        match number {
+            1 => Left,
+            2 => Middle,
+            3 => Right,
+            4 => X1,
+            5 => X2,
+            n => Other(n),
+        }
+    }
+
+    /// Returns the name of this button.
+    pub fn name(&self) -> Cow<'static, str> {
+        use self::MouseButton::*;
+        match *self {
+            Left => Cow::Borrowed("left"),
+            Middle => Cow::Borrowed("middle"),
+            Right => Cow::Borrowed("right"),
+            X1 => Cow::Borrowed("x1"),
+            X2 => Cow::Borrowed("x2"),
+            Other(n) => n.to_string().into(),
+        }
+    }
+
+    /// Attempts to find a mouse button with the given name.
+    ///
+    /// Button numbers (ie: `1` or `6`) are accepted as names too.
+    pub fn from_name(name: &str) -> Option<MouseButton> {
+        use self::MouseButton::*;
+        Some(match name {
+            "left" => Left,
+            "middle" => Middle,
+            "right" => Right,
+            "x1" => X1,
+            "x2" => X2,
+            _ => {
+                match name.parse::<u8>() {
+                    Ok(0) | Err(_) => return None,
+                    Ok(n) => MouseButton::from_number(n),
+                }
+            }
+        })
+    }
+}
+
+/// A direction the mouse wheel can be scrolled in.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum WheelDirection {
+    /// Scrolling away from the user.
+    Up,
+    /// Scrolling towards the user.
+    Down,
+    /// Scrolling (or tilting the wheel) to the left.
+    Left,
+    /// Scrolling (or tilting the wheel) to the right.
+    Right,
+}
+
+impl WheelDirection {
+    /// Returns the name of this direction.
+    pub fn name(&self) -> &'static str {
+        use self::WheelDirection::*;
+        match *self {
+            Up => "up",
+            Down => "down",
+            Left => "left",
+            Right => "right",
+        }
+    }
+
+    /// Attempts to find a wheel direction with the given name.
+    pub fn from_name(name: &str) -> Option<WheelDirection> {
+        use self::WheelDirection::*;
+        Some(match name {
+            "up" => Up,
+            "down" => Down,
+            "left" => Left,
+            "right" => Right,
+            _ => return None,
+        })
+    }
+
+    /// Returns the direction opposite to this one.
+    pub fn opposite(&self) -> WheelDirection {
+        use self::WheelDirection::*;
+        match *self {
+            Up => Down,
+            Down => Up,
+            Left => Right,
+            Right => Left,
+        }
+    }
+}
+
+/// The accumulated movement of the mouse wheel in a single game frame.
+///
+/// Positive values are up and to the right. The movement is measured in
+/// notches, but can be fractional with high-resolution wheels and
+/// touchpads. Values are compared bit for bit, so that input states can be
+/// `Eq`.
+#[derive(Debug, Clone, Default)]
+pub struct WheelValue {
+    /// The horizontal movement in notches.
+    pub x: f32,
+    /// The vertical movement in notches.
+    pub y: f32,
+}
+
+impl WheelValue {
+    /// Creates a new wheel value without any movement.
+    pub fn new() -> WheelValue {
+        WheelValue { x: 0.0, y: 0.0 }
+    }
+
+    /// Adds a number of notches in the given direction to the movement.
+    pub fn add(&mut self, direction: WheelDirection, notches: f32) {
+        match direction {
+            WheelDirection::Up => self.y += notches,
+            WheelDirection::Down => self.y -= notches,
+            WheelDirection::Left => self.x -= notches,
+            WheelDirection::Right => self.x += notches,
+        }
+    }
+}
+
+impl PartialEq for WheelValue {
+    fn eq(&self, other: &WheelValue) -> bool {
+        same_bits(self.x, other.x) && same_bits(self.y, other.y)
+    }
+}
+
+impl Eq for WheelValue {}
@@ -0,0 +1,706 @@
+//! Functionality to record input and replay it.
+//!
+//! There are two kinds of recordings:
+//!
+//! - A `Recording` holds the raw input events of a session, with the frame
+//!   each happened in. Replaying it through the same input map or context
+//!   stack reproduces the session (ie: for bug reports). The reproduction is
+//!   exact if the map has a frame time (see `InputMap::set_frame_time`);
+//!   otherwise time-based windows and hold times follow the clock during the
+//!   replay.
+//! - A `StateRecording` holds the action state of every frame, after
+//!   mapping. It doesn't need an input map to play back, and keeps working
+//!   when the player rebinds their controls (ie: for attract modes).
+//!
+//! Both can be written to and read from a compact binary file, which starts
+//! with a magic number and a format version.
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use super::key::Key;
+use super::mouse::{MouseButton, WheelDirection};
+use super::gamepad::{GamepadButton, GamepadAxis};
+use super::signal::Signal;
+use super::text::TextChange;
+use super::description::{Keytype, Mods, KeyDesc};
+use super::change::{DescribeInputChanges, ButtonChange, InputChange};
+use super::map::MapEvents;
+use super::state::{InputState, NamedIndex, AdvanceFrame, ButtonValue};
+use super::encoding::{BitWriter, BitReader, DecodeError};
+
+const CHANGES_MAGIC: &[u8; 4] = b"DLGC";
+const STATES_MAGIC: &[u8; 4] = b"DLGS";
+const FORMAT_VERSION: u8 = 2;
+
+/// A recording of the input events of a session, with the frame that each
+/// event happened in.
+///
+/// The changes of each event are kept together, so that the replay applies
+/// them as one event (ie: a key reported both as a key code and as a scan
+/// code is still a single press for double taps).
+///
+/// Call `advance_frame` on the recording whenever it is called on the input
+/// state.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Recording {
+    events: Vec<(u64, Vec<InputChange>)>,
+    frame: u64,
+}
+
+impl Recording {
+    /// Creates an empty recording, starting at frame 0.
+    pub fn new() -> Recording {
+        Recording::default()
+    }
+
+    /// Records the changes described by the event in the current frame.
+    ///
+    /// Events without any changes are not recorded.
+    pub fn record<E: DescribeInputChanges>(&mut self, event: &E) {
+        let mut changes = Vec::new();
+        event.describe_changes(|change| changes.push(change));
+        if !changes.is_empty() {
+            self.events.push((self.frame, changes));
+        }
+    }
+
+    /// Returns the current frame, which is also the length of the recording
+    /// in frames.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns the recorded events, as their frames and changes, in order.
+    pub fn events(&self) -> &[(u64, Vec<InputChange>)] {
+        &self.events
+    }
+
+    /// Writes the recording in the binary recording format.
+    ///
+    /// The names of the keys, buttons, axes and signals in the recording are
+    /// listed once, and changes refer to them by their position in the list.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut names = Vec::new();
+        let mut body = Vec::new();
+        write_varint(&mut body, self.frame)?;
+        write_varint(&mut body, self.events.len() as u64)?;
+        let mut last_frame = 0;
+        for &(frame, ref changes) in &self.events {
+            write_varint(&mut body, frame - last_frame)?;
+            write_varint(&mut body, changes.len() as u64)?;
+            for change in changes {
+                write_change(&mut body, change, &mut names)?;
+            }
+            last_frame = frame;
+        }
+        writer.write_all(CHANGES_MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        write_varint(writer, names.len() as u64)?;
+        for name in &names {
+            write_str(writer, name)?;
+        }
+        writer.write_all(&body)
+    }
+
+    /// Reads a recording in the binary recording format.
+    ///
+    /// Fails with `InvalidData` if the data isn't a recording, or was written
+    /// by an incompatible version.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Recording> {
+        read_header(reader, CHANGES_MAGIC)?;
+        let names = (0..read_varint(reader)?)
+            .map(|_| read_string(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+        let end_frame = read_varint(reader)?;
+        let len = read_varint(reader)?;
+        let mut events = Vec::new();
+        let mut frame: u64 = 0;
+        for _ in 0..len {
+            frame = frame.checked_add(read_varint(reader)?)
+                .ok_or_else(|| invalid_data("frame number too large"))?;
+            let changes = (0..read_varint(reader)?)
+                .map(|_| read_change(reader, &names))
+                .collect::<io::Result<Vec<_>>>()?;
+            events.push((frame, changes));
+        }
+        if frame > end_frame {
+            return Err(invalid_data("event recorded after the end of the recording"));
+        }
+        Ok(Recording {
+            events,
+            frame: end_frame,
+        })
+    }
+}
+
+impl AdvanceFrame for Recording {
+    fn advance_frame(&mut self) {
+        self.frame += 1;
+    }
+}
+
+/// Plays a recording back through an input map or a context stack.
+///
+/// Every frame, call `apply` and then `advance_frame` on the replay, the
+/// input map and the input state, in the same order as when recording.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    recording: Recording,
+    next: usize,
+    frame: u64,
+}
+
+impl Replay {
+    /// Creates a replay that starts at the first frame of the recording.
+    pub fn new(recording: Recording) -> Replay {
+        Replay {
+            recording,
+            next: 0,
+            frame: 0,
+        }
+    }
+
+    /// Returns the current frame of the replay.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns whether every frame of the recording has been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frame
+    }
+
+    /// Applies the events recorded in the current frame to the input state,
+    /// through an input map or a context stack.
+    pub fn apply<M, S>(&mut self, map: &mut M, state: &mut S)
+        where M: MapEvents<S>
+    {
+        while let Some(&(frame, ref changes)) = self.recording.events.get(self.next) {
+            if frame > self.frame {
+                break;
+            }
+            map.apply_event(changes, state);
+            self.next += 1;
+        }
+    }
+
+    /// Returns the recording being replayed.
+    pub fn into_recording(self) -> Recording {
+        self.recording
+    }
+}
+
+impl AdvanceFrame for Replay {
+    fn advance_frame(&mut self) {
+        self.frame += 1;
+    }
+}
+
+/// The recorded action state of one frame, in the order of the `all()`
+/// lists of the ids.
+///
+/// Buttons only keep the parts that are encoded (see `EncodeState`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Snapshot {
+    buttons: Vec<ButtonValue>,
+    signals: Vec<bool>,
+    axes: Vec<f32>,
+    vectors: Vec<(f32, f32)>,
+}
+
+impl Snapshot {
+    /// Records what the game can see of the state.
+    pub(crate) fn of<S: InputState>(state: &mut S) -> Snapshot {
+        let buttons = S::ButtonId::all()
+            .iter()
+            .map(|id| {
+                let mut button = ButtonValue::default();
+                copy_recorded_parts(state.get_button(id), &mut button);
+                button
+            })
+            .collect();
+        let signals = S::SignalId::all().iter().map(|id| *state.get_signal(id)).collect();
+        let axes = S::AxisId::all().iter().map(|id| state.get_axis(id).value).collect();
+        let vectors = S::VectorId::all().iter().map(|id| state.get_vector(id).value).collect();
+        Snapshot {
+            buttons,
+            signals,
+            axes,
+            vectors,
+        }
+    }
+}
+
+/// Copies the parts of a button that are recorded.
+fn copy_recorded_parts(from: &ButtonValue, to: &mut ButtonValue) {
+    to.held = from.held;
+    to.pressed = from.pressed;
+    to.released = from.released;
+    to.double_pressed = from.double_pressed;
+    to.press_count = from.press_count;
+    to.release_count = from.release_count;
+    to.repeats = from.repeats;
+}
+
+/// A recording of the action state of every frame of a session.
+///
+/// Only what the game reacts to is recorded: whether buttons are held,
+/// pressed and released (with their counts, repeats and double taps),
+/// signals, and axis and vector values. Hold times and buffered presses
+/// are not.
+///
+/// The file lists the action names, so it can be read after actions are
+/// added or reordered, but not after a recorded action is removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateRecording<S: InputState> {
+    frames: Vec<Snapshot>,
+    _state: PhantomData<S>,
+}
+
+impl<S: InputState> StateRecording<S> {
+    /// Creates an empty state recording.
+    pub fn new() -> StateRecording<S> {
+        StateRecording {
+            frames: Vec::new(),
+            _state: PhantomData,
+        }
+    }
+
+    /// Returns the number of recorded frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns whether no frames are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Records the state as the next frame. Call this after applying the
+    /// events of a frame, and before advancing the state.
+    pub fn record(&mut self, state: &mut S) {
+        self.frames.push(Snapshot::of(state));
+    }
+
+    /// Writes the recorded state of the given frame to the input state.
+    ///
+    /// Returns false if the frame wasn't recorded.
+    pub fn restore(&self, frame: usize, state: &mut S) -> bool {
+        let snapshot = match self.frames.get(frame) {
+            Some(snapshot) => snapshot,
+            None => return false,
+        };
+        for (id, recorded) in S::ButtonId::all().iter().zip(&snapshot.buttons) {
+            copy_recorded_parts(recorded, state.get_button(id));
+        }
+        for (id, &recorded) in S::SignalId::all().iter().zip(&snapshot.signals) {
+            *state.get_signal(id) = recorded;
+        }
+        for (id, &recorded) in S::AxisId::all().iter().zip(&snapshot.axes) {
+            state.get_axis(id).value = recorded;
+        }
+        for (id, &recorded) in S::VectorId::all().iter().zip(&snapshot.vectors) {
+            state.get_vector(id).value = recorded;
+        }
+        true
+    }
+
+    /// Writes the recording in the binary state recording format.
+    ///
+    /// The frames are encoded like `EncodeState::encode_bits`, in the order
+    /// of the action names listed in the file.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(STATES_MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        write_names::<W, S::ButtonId>(writer)?;
+        write_names::<W, S::SignalId>(writer)?;
+        write_names::<W, S::AxisId>(writer)?;
+        write_names::<W, S::VectorId>(writer)?;
+        write_varint(writer, self.frames.len() as u64)?;
+        let mut bits = BitWriter::new();
+        for snapshot in &self.frames {
+            for button in &snapshot.buttons {
+                bits.write_button(button);
+            }
+            for &signal in &snapshot.signals {
+                bits.write_bit(signal);
+            }
+            for &axis in &snapshot.axes {
+                bits.write_f32(axis);
+            }
+            for &(x, y) in &snapshot.vectors {
+                bits.write_f32(x);
+                bits.write_f32(y);
+            }
+        }
+        write_bytes(writer, &bits.into_bytes())
+    }
+
+    /// Reads a recording in the binary state recording format.
+    ///
+    /// Fails with `InvalidData` if the data isn't a state recording, was
+    /// written by an incompatible version, has actions that the state
+    /// doesn't have, or has frames without any actions.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<StateRecording<S>> {
+        read_header(reader, STATES_MAGIC)?;
+        let buttons = read_names::<R, S::ButtonId>(reader)?;
+        let signals = read_names::<R, S::SignalId>(reader)?;
+        let axes = read_names::<R, S::AxisId>(reader)?;
+        let vectors = read_names::<R, S::VectorId>(reader)?;
+        let len = read_varint(reader)?;
+        let bytes = read_bytes(reader)?;
+        // Each frame takes at least 6 bits per button, 1 per signal, 32 per
+        // axis and 64 per vector, so a corrupt length can't make it read more
+        // frames than the data holds.
+        let frame_bits = 6 * buttons.len() + signals.len() + 32 * axes.len() + 64 * vectors.len();
+        if len > 0 && (frame_bits == 0 || len > (bytes.len() * 8 / frame_bits) as u64) {
+            return Err(invalid_data("more frames than the data holds"));
+        }
+        let mut bits = BitReader::new(&bytes);
+        let mut frames = Vec::new();
+        for _ in 0..len {
+            let mut snapshot = Snapshot {
+                buttons: vec![ButtonValue::default(); S::ButtonId::all().len()],
+                signals: vec![false; S::SignalId::all().len()],
+                axes: vec![0.0; S::AxisId::all().len()],
+                vectors: vec![(0.0, 0.0); S::VectorId::all().len()],
+            };
+            read_snapshot(&mut bits, &mut snapshot, [&buttons, &signals, &axes, &vectors])
+                .map_err(|_| invalid_data("frames cut short"))?;
+            frames.push(snapshot);
+        }
+        bits.finish().map_err(|_| invalid_data("data left after the last frame"))?;
+        Ok(StateRecording {
+            frames,
+            _state: PhantomData,
+        })
+    }
+}
+
+/// Reads a frame of a state recording into the snapshot, given the
+/// positions of the recorded buttons, signals, axes and vectors.
+fn read_snapshot(bits: &mut BitReader,
+                 snapshot: &mut Snapshot,
+                 indices: [&[usize]; 4])
+                 -> Result<(), DecodeError> {
+    for &index in indices[0] {
+        bits.read_button(&mut snapshot.buttons[index])?;
+    }
+    for &index in indices[1] {
+        snapshot.signals[index] = bits.read_bit()?;
+    }
+    for &index in indices[2] {
+        snapshot.axes[index] = bits.read_f32()?;
+    }
+    for &index in indices[3] {
+        snapshot.vectors[index] = (bits.read_f32()?, bits.read_f32()?);
+    }
+    Ok(())
+}
+
+impl<S: InputState> Default for StateRecording<S> {
+    fn default() -> StateRecording<S> {
+        StateRecording::new()
+    }
+}
+
+/// Writes the names of every value of the index.
+fn write_names<W: Write, I: NamedIndex>(writer: &mut W) -> io::Result<()> {
+    write_varint(writer, I::all().len() as u64)?;
+    for id in I::all() {
+        write_str(writer, id.name())?;
+    }
+    Ok(())
+}
+
+/// Reads a list of names, and returns the position of each in `I::all()`.
+fn read_names<R: Read, I: NamedIndex>(reader: &mut R) -> io::Result<Vec<usize>> {
+    let len = read_varint(reader)?;
+    let mut indices = Vec::new();
+    for _ in 0..len {
+        let name = read_string(reader)?;
+        match I::all().iter().position(|id| id.name() == name) {
+            Some(index) => indices.push(index),
+            None => return Err(invalid_data(&format!("unknown action '{}'", name))),
+        }
+    }
+    Ok(indices)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_header<R: Read>(reader: &mut R, magic: &[u8; 4]) -> io::Result<()> {
+    let mut header = [0; 5];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != magic {
+        return Err(invalid_data("not a dalgi recording"));
+    }
+    if header[4] != FORMAT_VERSION {
+        return Err(invalid_data(&format!("unsupported recording version {}", header[4])));
+    }
+    Ok(())
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// Writes an unsigned number in as few bytes as possible (LEB128).
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Reads a number written by `write_varint`, failing if it doesn't fit in
+/// 64 bits.
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for i in 0..10 {
+        let byte = read_u8(reader)?;
+        // The tenth byte holds the last bit, and can't be continued.
+        if i == 9 && byte > 1 {
+            break;
+        }
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("number too large"))
+}
+
+fn write_f32<W: Write>(writer: &mut W, value: f32) -> io::Result<()> {
+    write_u32(writer, value.to_bits())
+}
+
+fn read_f32<R: Read>(reader: &mut R) -> io::Result<f32> {
+    read_u32(reader).map(f32::from_bits)
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as u32))
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write_varint(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = read_varint(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "data cut short"));
+    }
+    Ok(bytes)
+}
+
+fn write_str<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    write_bytes(writer, text.as_bytes())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader)?).map_err(|_| invalid_data("text is not UTF-8"))
+}
+
+fn write_button_change<W: Write>(writer: &mut W, change: ButtonChange) -> io::Result<()> {
+    writer.write_all(&[match change {
+                           ButtonChange::Pressed => 0,
+                           ButtonChange::Released => 1,
+                           ButtonChange::Repeated => 2,
+                       }])
+}
+
+fn read_button_change<R: Read>(reader: &mut R) -> io::Result<ButtonChange> {
+    Ok(match read_u8(reader)? {
+        0 => ButtonChange::Pressed,
+        1 => ButtonChange::Released,
+        2 => ButtonChange::Repeated,
+        _ => return Err(invalid_data("unknown button change")),
+    })
+}
+
+/// Writes the position of the name in the names of the recording, adding it
+/// if it isn't there yet.
+fn write_named<W: Write>(writer: &mut W, names: &mut Vec<String>, name: &str) -> io::Result<()> {
+    let index = match names.iter().position(|known| known == name) {
+        Some(index) => index,
+        None => {
+            names.push(name.to_string());
+            names.len() - 1
+        }
+    };
+    write_varint(writer, index as u64)
+}
+
+/// Reads the position of a name in the names of the recording and looks the
+/// name up, failing with a description of the kind of name if it is unknown.
+fn read_named<R, T, F>(reader: &mut R, names: &[String], kind: &str, from_name: F) -> io::Result<T>
+    where R: Read,
+          F: FnOnce(&str) -> Option<T>
+{
+    let name = match names.get(read_varint(reader)? as usize) {
+        Some(name) => name,
+        None => return Err(invalid_data(&format!("{} name out of range", kind))),
+    };
+    from_name(name).ok_or_else(|| invalid_data(&format!("unknown {} '{}'", kind, name)))
+}
+
+fn write_change<W: Write>(writer: &mut W,
+                          change: &InputChange,
+                          names: &mut Vec<String>)
+                          -> io::Result<()> {
+    match *change {
+        InputChange::Key(desc, button_change) => {
+            writer.write_all(&[0])?;
+            // Keys are written by name, so that adding keys doesn't change
+            // their encoding.
+            write_named(writer, names, &desc.key.name())?;
+            let keytype = match desc.keytype {
+                Keytype::Keycode => 0,
+                Keytype::Scancode => 1,
+            };
+            let mods = desc.mods.bits();
+            writer.write_all(&[keytype, mods as u8, (mods >> 8) as u8])?;
+            write_button_change(writer, button_change)
+        }
+        InputChange::Signal(signal) => {
+            writer.write_all(&[1])?;
+            write_named(writer, names, signal.name())
+        }
+        InputChange::MouseButton(button, button_change) => {
+            writer.write_all(&[2])?;
+            write_named(writer, names, &button.name())?;
+            write_button_change(writer, button_change)
+        }
+        InputChange::Wheel(direction, notches) => {
+            writer.write_all(&[3])?;
+            write_named(writer, names, direction.name())?;
+            write_f32(writer, notches)
+        }
+        InputChange::GamepadButton(index, button, button_change) => {
+            writer.write_all(&[4, index])?;
+            write_named(writer, names, button.name())?;
+            write_button_change(writer, button_change)
+        }
+        InputChange::GamepadAxis(index, axis, value) => {
+            writer.write_all(&[5, index])?;
+            write_named(writer, names, axis.name())?;
+            write_f32(writer, value)
+        }
+        InputChange::Text(ref text) => {
+            writer.write_all(&[6])?;
+            match *text {
+                TextChange::Commit(ref text) => {
+                    writer.write_all(&[0])?;
+                    write_str(writer, text)
+                }
+                TextChange::CompositionStart => writer.write_all(&[1]),
+                TextChange::CompositionUpdate { ref text, ref cursor } => {
+                    writer.write_all(&[2])?;
+                    write_str(writer, text)?;
+                    write_varint(writer, cursor.start as u64)?;
+                    write_varint(writer, cursor.end as u64)
+                }
+                TextChange::CompositionEnd => writer.write_all(&[3]),
+            }
+        }
+    }
+}
+
+fn read_change<R: Read>(reader: &mut R, names: &[String]) -> io::Result<InputChange> {
+    Ok(match read_u8(reader)? {
+        0 => {
+            let key = read_named(reader, names, "key", Key::from_name)?;
+            let mut bytes = [0; 3];
+            reader.read_exact(&mut bytes)?;
+            let keytype = match bytes[0] {
+                0 => Keytype::Keycode,
+                1 => Keytype::Scancode,
+                _ => return Err(invalid_data("unknown key type")),
+            };
+            let mut desc = KeyDesc::new(key).mods(Mods::from_bits(bytes[1] as u16 | (bytes[2] as u16) << 8));
+            desc.keytype = keytype;
+            InputChange::Key(desc, read_button_change(reader)?)
+        }
+        1 => InputChange::Signal(read_named(reader, names, "signal", Signal::from_name)?),
+        2 => {
+            let button = read_named(reader, names, "mouse button", MouseButton::from_name)?;
+            InputChange::MouseButton(button, read_button_change(reader)?)
+        }
+        3 => {
+            let direction = read_named(reader, names, "wheel direction", WheelDirection::from_name)?;
+            InputChange::Wheel(direction, read_f32(reader)?)
+        }
+        4 => {
+            let index = read_u8(reader)?;
+            let button = read_named(reader, names, "gamepad button", GamepadButton::from_name)?;
+            InputChange::GamepadButton(index, button, read_button_change(reader)?)
+        }
+        5 => {
+            let index = read_u8(reader)?;
+            let axis = read_named(reader, names, "gamepad axis", GamepadAxis::from_name)?;
+            InputChange::GamepadAxis(index, axis, read_f32(reader)?)
+        }
+        6 => {
+            InputChange::Text(match read_u8(reader)? {
+                0 => TextChange::Commit(read_string(reader)?),
+                1 => TextChange::CompositionStart,
+                2 => {
+                    let text = read_string(reader)?;
+                    let start = read_varint(reader)? as usize;
+                    let end = read_varint(reader)? as usize;
+                    TextChange::CompositionUpdate {
+                        text,
+                        cursor: start..end,
+                    }
+                }
+                3 => TextChange::CompositionEnd,
+                _ => return Err(invalid_data("unknown text change")),
+            })
+        }
+        _ => return Err(invalid_data("unknown input change")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_varint, write_varint};
+
+    #[test]
+    fn varints_round_trip() {
+        for &value in &[0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value).unwrap();
+            assert!(bytes.len() <= 10);
+            assert_eq!(read_varint(&mut &bytes[..]).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn varints_beyond_64_bits_are_rejected() {
+        let mut too_long = vec![0x80; 10];
+        too_long.push(0);
+        assert!(read_varint(&mut &too_long[..]).is_err());
+        let mut too_large = vec![0xff; 9];
+        too_large.push(0x02);
+        assert!(read_varint(&mut &too_large[..]).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Functionality to buffer input states for rollback netcode.
+
+use std::collections::VecDeque;
+use super::state::{InputState, AdvanceFrame};
+use super::replay::Snapshot;
+
+/// How many frames ahead of the current one input is accepted by default
+/// (ten seconds at 60 frames per second).
+const DEFAULT_MAX_AHEAD: u32 = 600;
+
+/// The input state of a player in one frame.
+#[derive(Debug, Clone)]
+struct Slot<S> {
+    state: S,
+    /// Whether the state is the real input, rather than a prediction.
+    confirmed: bool,
+}
+
+/// The input states of one player, indexed by frame, for rollback netcode.
+///
+/// Frames that have no input yet (ie: remote input that hasn't arrived) are
+/// predicted by repeating the last known state: buttons stay held, but
+/// aren't pressed or released again. When the real input arrives and
+/// differs from the prediction, the frame is reported through
+/// `take_misprediction`, and the game should resimulate from that frame
+/// using the states in the buffer.
+///
+/// States are compared by what the game can see of them (ie: whether buttons
+/// are held, pressed and released, signals and analog values), not by hold
+/// times.
+///
+/// Input for frames too far ahead of the current one (see `set_max_ahead`)
+/// is ignored, so that a bad frame number can't make the buffer grow
+/// without bounds.
+///
+/// Call `advance_frame` on the buffer whenever the game moves to a new
+/// frame.
+#[derive(Debug, Clone)]
+pub struct RollbackBuffer<S>
+    where S: InputState + Clone + Default
+{
+    slots: VecDeque<Slot<S>>,
+    /// The frame of the first slot.
+    first_frame: u64,
+    frame: u64,
+    delay: u64,
+    max_ahead: u64,
+    /// The frames before this one have been read by the game.
+    read_until: u64,
+    misprediction: Option<u64>,
+}
+
+impl<S> RollbackBuffer<S>
+    where S: InputState + Clone + Default
+{
+    /// Creates a buffer for a remote player.
+    pub fn new() -> RollbackBuffer<S> {
+        RollbackBuffer::with_delay(0)
+    }
+
+    /// Creates a buffer for a local player, whose input is used the given
+    /// number of frames after it is added (see `push_local`).
+    ///
+    /// The first frames, before any input can take effect, have the default
+    /// state.
+    pub fn with_delay(delay: u32) -> RollbackBuffer<S> {
+        let slots = (0..delay)
+            .map(|_| {
+                Slot {
+                    state: S::default(),
+                    confirmed: true,
+                }
+            })
+            .collect();
+        RollbackBuffer {
+            slots,
+            first_frame: 0,
+            frame: 0,
+            delay: delay as u64,
+            max_ahead: DEFAULT_MAX_AHEAD as u64,
+            read_until: 0,
+            misprediction: None,
+        }
+    }
+
+    /// Returns the current frame.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns the local input delay in frames.
+    pub fn delay(&self) -> u32 {
+        self.delay as u32
+    }
+
+    /// Sets how many frames past the current one (and the input delay) input
+    /// is accepted for. The default is 600 frames.
+    pub fn set_max_ahead(&mut self, frames: u32) {
+        self.max_ahead = frames as u64;
+    }
+
+    /// Returns how many frames past the current one (and the input delay)
+    /// input is accepted for.
+    pub fn max_ahead(&self) -> u32 {
+        self.max_ahead as u32
+    }
+
+    /// Returns whether the frame is too far ahead to be buffered.
+    fn is_too_far_ahead(&self, frame: u64) -> bool {
+        frame > self.frame + self.delay + self.max_ahead
+    }
+
+    /// Adds the local input of the current frame, to be used after the input
+    /// delay. Returns the frame it is used in.
+    pub fn push_local(&mut self, state: &S) -> u64 {
+        let frame = self.frame + self.delay;
+        self.confirm(frame, state.clone());
+        frame
+    }
+
+    /// Adds the real input of a frame (ie: received from a remote player).
+    ///
+    /// If the game already read a different prediction of the frame, the
+    /// frame is reported by `take_misprediction`. Input for frames that were
+    /// already confirmed or discarded, or that are too far ahead
+    /// (see `set_max_ahead`), is ignored.
+    pub fn confirm(&mut self, frame: u64, state: S) {
+        if frame < self.first_frame || self.is_too_far_ahead(frame) {
+            return;
+        }
+        let index = self.fill_to(frame);
+        if self.slots[index].confirmed {
+            return;
+        }
+        let predicted = self.slots[index].state.clone();
+        let mut state = state;
+        if frame < self.read_until && !same_input(predicted, &mut state) {
+            self.misprediction = Some(self.misprediction.map_or(frame, |earliest| earliest.min(frame)));
+        }
+        self.slots[index] = Slot {
+            state,
+            confirmed: true,
+        };
+        // Predict the following frames from the new input.
+        for index in index + 1..self.slots.len() {
+            if self.slots[index].confirmed {
+                break;
+            }
+            let state = predict(&self.slots[index - 1].state);
+            self.slots[index].state = state;
+        }
+    }
+
+    /// Returns the input state of the frame: the real input if it is known,
+    /// and a prediction if it isn't.
+    ///
+    /// Returns `None` if the frame was discarded, or is too far ahead
+    /// (see `set_max_ahead`).
+    pub fn state(&mut self, frame: u64) -> Option<&S> {
+        if frame < self.first_frame || self.is_too_far_ahead(frame) {
+            return None;
+        }
+        let index = self.fill_to(frame);
+        self.read_until = self.read_until.max(frame + 1);
+        Some(&self.slots[index].state)
+    }
+
+    /// Returns the input state of the current frame.
+    pub fn current(&mut self) -> &S {
+        let frame = self.frame;
+        self.state(frame).expect("The current frame was discarded")
+    }
+
+    /// Returns whether the real input of the frame is known.
+    pub fn is_confirmed(&self, frame: u64) -> bool {
+        frame >= self.first_frame &&
+        self.slots.get((frame - self.first_frame) as usize).is_some_and(|slot| slot.confirmed)
+    }
+
+    /// Returns the last frame up to which all the real input is known, if any.
+    pub fn confirmed_until(&self) -> Option<u64> {
+        let confirmed = self.slots.iter().take_while(|slot| slot.confirmed).count() as u64;
+        if self.first_frame + confirmed == 0 {
+            None
+        } else {
+            Some(self.first_frame + confirmed - 1)
+        }
+    }
+
+    /// Returns the earliest frame that was predicted wrongly since the last
+    /// call, if any. The game should roll back to this frame and resimulate.
+    pub fn take_misprediction(&mut self) -> Option<u64> {
+        self.misprediction.take()
+    }
+
+    /// Discards the states of the frames before the given one (ie: once they
+    /// are confirmed for every player and will never be resimulated).
+    pub fn discard_before(&mut self, frame: u64) {
+        while self.first_frame < frame && self.slots.len() > 1 {
+            self.slots.pop_front();
+            self.first_frame += 1;
+        }
+    }
+
+    /// Adds predicted slots up to the frame, and returns its index.
+    fn fill_to(&mut self, frame: u64) -> usize {
+        let index = (frame - self.first_frame) as usize;
+        while self.slots.len() <= index {
+            let state = match self.slots.back() {
+                Some(slot) => predict(&slot.state),
+                None => S::default(),
+            };
+            self.slots.push_back(Slot {
+                state,
+                confirmed: false,
+            });
+        }
+        index
+    }
+}
+
+impl<S> Default for RollbackBuffer<S>
+    where S: InputState + Clone + Default
+{
+    fn default() -> RollbackBuffer<S> {
+        RollbackBuffer::new()
+    }
+}
+
+impl<S> AdvanceFrame for RollbackBuffer<S>
+    where S: InputState + Clone + Default
+{
+    fn advance_frame(&mut self) {
+        self.frame += 1;
+    }
+}
+
+/// Predicts the state of the frame after the given one, by repeating it.
+fn predict<S: InputState + Clone>(state: &S) -> S {
+    let mut state = state.clone();
+    state.advance_frame();
+    state
+}
+
+/// Returns whether the game would see the same input in both states.
+fn same_input<S: InputState>(mut state: S, other: &mut S) -> bool {
+    Snapshot::of(&mut state) == Snapshot::of(other)
+}
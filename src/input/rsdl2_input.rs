@@ -11,14 +11,124 @@ fn map_keycode(key: Keycode) -> Key {
         Right => Key::Right,
         Space => Key::Space,
         Return => Key::Return,
+        Tab => Key::Tab,
+        Backspace => Key::Backspace,
+        Escape => Key::Escape,
+        Delete => Key::Delete,
+        Insert => Key::Insert,
+        Home => Key::Home,
+        End => Key::End,
+        PageUp => Key::PageUp,
+        PageDown => Key::PageDown,
+        Zero => Key::Zero,
         One => Key::One,
         Two => Key::Two,
         Three => Key::Three,
         Four => Key::Four,
-        W => Key::W,
+        Five => Key::Five,
+        Six => Key::Six,
+        Seven => Key::Seven,
+        Eight => Key::Eight,
+        Nine => Key::Nine,
         A => Key::A,
-        S => Key::S,
+        B => Key::B,
+        C => Key::C,
         D => Key::D,
+        E => Key::E,
+        F => Key::F,
+        G => Key::G,
+        H => Key::H,
+        I => Key::I,
+        J => Key::J,
+        K => Key::K,
+        L => Key::L,
+        M => Key::M,
+        N => Key::N,
+        O => Key::O,
+        P => Key::P,
+        Q => Key::Q,
+        R => Key::R,
+        S => Key::S,
+        T => Key::T,
+        U => Key::U,
+        V => Key::V,
+        W => Key::W,
+        X => Key::X,
+        Y => Key::Y,
+        Z => Key::Z,
+        Minus => Key::Minus,
+        Equals => Key::Equals,
+        LeftBracket => Key::LeftBracket,
+        RightBracket => Key::RightBracket,
+        Backslash => Key::Backslash,
+        Semicolon => Key::Semicolon,
+        Quote => Key::Apostrophe,
+        Backquote => Key::Grave,
+        Comma => Key::Comma,
+        Period => Key::Period,
+        Slash => Key::Slash,
+        F1 => Key::F1,
+        F2 => Key::F2,
+        F3 => Key::F3,
+        F4 => Key::F4,
+        F5 => Key::F5,
+        F6 => Key::F6,
+        F7 => Key::F7,
+        F8 => Key::F8,
+        F9 => Key::F9,
+        F10 => Key::F10,
+        F11 => Key::F11,
+        F12 => Key::F12,
+        F13 => Key::F13,
+        F14 => Key::F14,
+        F15 => Key::F15,
+        F16 => Key::F16,
+        F17 => Key::F17,
+        F18 => Key::F18,
+        F19 => Key::F19,
+        F20 => Key::F20,
+        F21 => Key::F21,
+        F22 => Key::F22,
+        F23 => Key::F23,
+        F24 => Key::F24,
+        Kp0 => Key::KpZero,
+        Kp1 => Key::KpOne,
+        Kp2 => Key::KpTwo,
+        Kp3 => Key::KpThree,
+        Kp4 => Key::KpFour,
+        Kp5 => Key::KpFive,
+        Kp6 => Key::KpSix,
+        Kp7 => Key::KpSeven,
+        Kp8 => Key::KpEight,
+        Kp9 => Key::KpNine,
+        KpDivide => Key::KpDivide,
+        KpMultiply => Key::KpMultiply,
+        KpMinus => Key::KpMinus,
+        KpPlus => Key::KpPlus,
+        KpEnter => Key::KpEnter,
+        KpPeriod => Key::KpPeriod,
+        KpEquals => Key::KpEquals,
+        CapsLock => Key::CapsLock,
+        NumLockClear => Key::NumLock,
+        ScrollLock => Key::ScrollLock,
+        PrintScreen => Key::PrintScreen,
+        Pause => Key::Pause,
+        Application => Key::Menu,
+        Mute => Key::Mute,
+        VolumeUp => Key::VolumeUp,
+        VolumeDown => Key::VolumeDown,
+        AudioPlay => Key::MediaPlay,
+        AudioStop => Key::MediaStop,
+        AudioNext => Key::MediaNext,
+        AudioPrev => Key::MediaPrev,
+        LShift => Key::LeftShift,
+        LCtrl => Key::LeftCtrl,
+        LAlt => Key::LeftAlt,
+        LGui => Key::LeftSuper,
+        RShift => Key::RightShift,
+        RCtrl => Key::RightCtrl,
+        RAlt => Key::RightAlt,
+        RGui => Key::RightSuper,
         _ => Key::Other(key as i32),
     }
 }
@@ -32,27 +142,402 @@ fn map_scancode(key: Scancode) -> Key {
         Right => Key::Right,
         Space => Key::Space,
         Return => Key::Return,
+        Tab => Key::Tab,
+        Backspace => Key::Backspace,
+        Escape => Key::Escape,
+        Delete => Key::Delete,
+        Insert => Key::Insert,
+        Home => Key::Home,
+        End => Key::End,
+        PageUp => Key::PageUp,
+        PageDown => Key::PageDown,
+        Zero => Key::Zero,
         One => Key::One,
         Two => Key::Two,
         Three => Key::Three,
         Four => Key::Four,
-        W => Key::W,
+        Five => Key::Five,
+        Six => Key::Six,
+        Seven => Key::Seven,
+        Eight => Key::Eight,
+        Nine => Key::Nine,
         A => Key::A,
-        S => Key::S,
+        B => Key::B,
+        C => Key::C,
         D => Key::D,
+        E => Key::E,
+        F => Key::F,
+        G => Key::G,
+        H => Key::H,
+        I => Key::I,
+        J => Key::J,
+        K => Key::K,
+        L => Key::L,
+        M => Key::M,
+        N => Key::N,
+        O => Key::O,
+        P => Key::P,
+        Q => Key::Q,
+        R => Key::R,
+        S => Key::S,
+        T => Key::T,
+        U => Key::U,
+        V => Key::V,
+        W => Key::W,
+        X => Key::X,
+        Y => Key::Y,
+        Z => Key::Z,
+        Minus => Key::Minus,
+        Equals => Key::Equals,
+        LeftBracket => Key::LeftBracket,
+        RightBracket => Key::RightBracket,
+        Backslash => Key::Backslash,
+        Semicolon => Key::Semicolon,
+        Apostrophe => Key::Apostrophe,
+        Grave => Key::Grave,
+        Comma => Key::Comma,
+        Period => Key::Period,
+        Slash => Key::Slash,
+        F1 => Key::F1,
+        F2 => Key::F2,
+        F3 => Key::F3,
+        F4 => Key::F4,
+        F5 => Key::F5,
+        F6 => Key::F6,
+        F7 => Key::F7,
+        F8 => Key::F8,
+        F9 => Key::F9,
+        F10 => Key::F10,
+        F11 => Key::F11,
+        F12 => Key::F12,
+        F13 => Key::F13,
+        F14 => Key::F14,
+        F15 => Key::F15,
+        F16 => Key::F16,
+        F17 => Key::F17,
+        F18 => Key::F18,
+        F19 => Key::F19,
+        F20 => Key::F20,
+        F21 => Key::F21,
+        F22 => Key::F22,
+        F23 => Key::F23,
+        F24 => Key::F24,
+        Kp0 => Key::KpZero,
+        Kp1 => Key::KpOne,
+        Kp2 => Key::KpTwo,
+        Kp3 => Key::KpThree,
+        Kp4 => Key::KpFour,
+        Kp5 => Key::KpFive,
+        Kp6 => Key::KpSix,
+        Kp7 => Key::KpSeven,
+        Kp8 => Key::KpEight,
+        Kp9 => Key::KpNine,
+        KpDivide => Key::KpDivide,
+        KpMultiply => Key::KpMultiply,
+        KpMinus => Key::KpMinus,
+        KpPlus => Key::KpPlus,
+        KpEnter => Key::KpEnter,
+        KpPeriod => Key::KpPeriod,
+        KpEquals => Key::KpEquals,
+        CapsLock => Key::CapsLock,
+        NumLockClear => Key::NumLock,
+        ScrollLock => Key::ScrollLock,
+        PrintScreen => Key::PrintScreen,
+        Pause => Key::Pause,
+        Application => Key::Menu,
+        Mute => Key::Mute,
+        VolumeUp => Key::VolumeUp,
+        VolumeDown => Key::VolumeDown,
+        AudioPlay => Key::MediaPlay,
+        AudioStop => Key::MediaStop,
+        AudioNext => Key::MediaNext,
+        AudioPrev => Key::MediaPrev,
+        LShift => Key::LeftShift,
+        LCtrl => Key::LeftCtrl,
+        LAlt => Key::LeftAlt,
+        LGui => Key::LeftSuper,
+        RShift => Key::RightShift,
+        RCtrl => Key::RightCtrl,
+        RAlt => Key::RightAlt,
+        RGui => Key::RightSuper,
         _ => Key::Other(key as i32),
     }
 }
 
+/// Maps the SDL `KMOD_*` bits of a key event to dalgi modifiers.
+fn map_mods(keymod: u16) -> Mods {
+    let mut mods = Mods::NONE;
+    let table = [(0x0001, Mods::LEFT_SHIFT),
+                 (0x0002, Mods::RIGHT_SHIFT),
+                 (0x0040, Mods::LEFT_CTRL),
+                 (0x0080, Mods::RIGHT_CTRL),
+                 (0x0100, Mods::LEFT_ALT),
+                 (0x0200, Mods::RIGHT_ALT),
+                 (0x0400, Mods::LEFT_SUPER),
+                 (0x0800, Mods::RIGHT_SUPER)];
+    for &(bit, flag) in table.iter() {
+        if keymod & bit != 0 {
+            mods |= flag;
+        }
+    }
+    mods
+}
+
 fn key_change(keysym: Keysym, state: ButtonChange) -> InputChange {
-    InputChange::Key(KeyDesc::new(map_keycode(keysym.keycode)), state)
+    InputChange::Key(KeyDesc::new(map_keycode(keysym.keycode))
+                         .mods(map_mods(keysym.keymod as u16)),
+                     state)
 }
 
 fn scan_change(keysym: Keysym, state: ButtonChange) -> InputChange {
-    InputChange::Key(KeyDesc::new(map_scancode(keysym.scancode)).scancode(),
+    InputChange::Key(KeyDesc::new(map_scancode(keysym.scancode))
+                         .scancode()
+                         .mods(map_mods(keysym.keymod as u16)),
                      state)
 }
 
+/// Maps an `SDL_GameControllerButton` value to a gamepad button.
+///
+/// SDL has no trigger buttons: see `Rsdl2Events`.
+fn map_controller_button(button: u8) -> Option<GamepadButton> {
+    use input::GamepadButton::*;
+    Some(match button {
+        0 => South,
+        1 => East,
+        2 => West,
+        3 => North,
+        4 => Back,
+        5 => Guide,
+        6 => Start,
+        7 => LeftStick,
+        8 => RightStick,
+        9 => LeftShoulder,
+        10 => RightShoulder,
+        11 => DPadUp,
+        12 => DPadDown,
+        13 => DPadLeft,
+        14 => DPadRight,
+        _ => return None,
+    })
+}
+
+/// Maps an `SDL_GameControllerAxis` value to a gamepad axis.
+fn map_controller_axis(axis: u8) -> Option<GamepadAxis> {
+    use input::GamepadAxis::*;
+    Some(match axis {
+        0 => LeftX,
+        1 => LeftY,
+        2 => RightX,
+        3 => RightY,
+        4 => LeftTrigger,
+        5 => RightTrigger,
+        _ => return None,
+    })
+}
+
+/// Returns the digital button of a trigger axis.
+fn trigger_button(axis: GamepadAxis) -> Option<GamepadButton> {
+    match axis {
+        GamepadAxis::LeftTrigger => Some(GamepadButton::LeftTrigger),
+        GamepadAxis::RightTrigger => Some(GamepadButton::RightTrigger),
+        _ => None,
+    }
+}
+
+/// How far a trigger has to be pulled to press its button, by default.
+const DEFAULT_TRIGGER_THRESHOLD: f32 = 0.5;
+
+/// Describes SDL events, keeping track of what single events don't tell.
+///
+/// SDL identifies game controllers by their joystick instance id, which grows
+/// every time a controller is reconnected. Each controller is given the
+/// lowest free pad index instead, which is freed when the controller is
+/// removed.
+///
+/// SDL only reports triggers as axes, so the trigger buttons
+/// (`GamepadButton::LeftTrigger` and `GamepadButton::RightTrigger`) are
+/// pressed when their axis reaches a threshold, and released when it goes
+/// back under it.
+///
+/// SDL doesn't report when an IME starts composing either, so a
+/// `TextChange::CompositionStart` is described before the first composition
+/// update after a composition ended or text was committed.
+///
+/// Controller events and composition starts are only described through
+/// `Rsdl2Events`: applying an SDL event directly to an input map ignores
+/// controllers, and only describes composition updates and ends.
+#[derive(Debug, Clone)]
+pub struct Rsdl2Events {
+    /// The instance id of the controller at each pad index.
+    pads: Vec<Option<i32>>,
+    /// The trigger buttons that are held, by pad index and axis.
+    held_triggers: Vec<(u8, GamepadAxis)>,
+    trigger_threshold: f32,
+    /// Whether the IME is composing text.
+    composing: bool,
+}
+
+impl Rsdl2Events {
+    /// Creates an event describer with no known controllers.
+    pub fn new() -> Rsdl2Events {
+        Rsdl2Events {
+            pads: Vec::new(),
+            held_triggers: Vec::new(),
+            trigger_threshold: DEFAULT_TRIGGER_THRESHOLD,
+            composing: false,
+        }
+    }
+
+    /// Sets how far (from 0 to 1) a trigger has to be pulled to press its
+    /// button.
+    pub fn set_trigger_threshold(&mut self, threshold: f32) {
+        self.trigger_threshold = threshold;
+    }
+
+    /// Returns how far a trigger has to be pulled to press its button.
+    pub fn trigger_threshold(&self) -> f32 {
+        self.trigger_threshold
+    }
+
+    /// Returns the pad index of the controller with the given joystick
+    /// instance id, if it has sent any events since it was connected.
+    pub fn pad_index(&self, instance_id: i32) -> Option<u8> {
+        self.pads.iter().position(|&pad| pad == Some(instance_id)).map(|index| index as u8)
+    }
+
+    /// Returns the pad index of the controller, giving it the lowest free one
+    /// if it doesn't have one yet.
+    ///
+    /// Returns `None` when every pad index is taken.
+    fn pad(&mut self, instance_id: i32) -> Option<u8> {
+        if let Some(index) = self.pad_index(instance_id) {
+            return Some(index);
+        }
+        match self.pads.iter().position(|pad| pad.is_none()) {
+            Some(index) => {
+                self.pads[index] = Some(instance_id);
+                Some(index as u8)
+            }
+            None if self.pads.len() <= u8::MAX as usize => {
+                self.pads.push(Some(instance_id));
+                Some((self.pads.len() - 1) as u8)
+            }
+            None => None,
+        }
+    }
+
+    /// Frees the pad index of a removed controller, and releases its
+    /// triggers.
+    fn remove_pad(&mut self, instance_id: i32, changes: &mut Vec<InputChange>) {
+        let index = match self.pad_index(instance_id) {
+            Some(index) => index,
+            None => return,
+        };
+        self.pads[index as usize] = None;
+        for &(pad, axis) in self.held_triggers.iter().filter(|&&(pad, _)| pad == index) {
+            if let Some(button) = trigger_button(axis) {
+                changes.push(InputChange::GamepadButton(pad, button, ButtonChange::Released));
+            }
+        }
+        self.held_triggers.retain(|&(pad, _)| pad != index);
+    }
+
+    /// Presses or releases the button of a trigger axis that crossed the
+    /// threshold.
+    fn update_trigger(&mut self,
+                      index: u8,
+                      axis: GamepadAxis,
+                      value: f32,
+                      changes: &mut Vec<InputChange>) {
+        let button = match trigger_button(axis) {
+            Some(button) => button,
+            None => return,
+        };
+        let was_held = self.held_triggers.contains(&(index, axis));
+        let held = value >= self.trigger_threshold;
+        if held && !was_held {
+            self.held_triggers.push((index, axis));
+            changes.push(InputChange::GamepadButton(index, button, ButtonChange::Pressed));
+        } else if !held && was_held {
+            self.held_triggers.retain(|&trigger| trigger != (index, axis));
+            changes.push(InputChange::GamepadButton(index, button, ButtonChange::Released));
+        }
+    }
+
+    /// Returns the changes described by the event.
+    pub fn changes(&mut self, event: &Event) -> Vec<InputChange> {
+        use rsdl2::events::EventKind::*;
+        let mut changes = Vec::new();
+        match event.kind {
+            ControllerButtonDown(ref event) => {
+                let button = map_controller_button(event.button as u8);
+                if let (Some(button), Some(index)) = (button, self.pad(event.which)) {
+                    changes.push(InputChange::GamepadButton(index, button, ButtonChange::Pressed));
+                }
+            }
+            ControllerButtonUp(ref event) => {
+                let button = map_controller_button(event.button as u8);
+                if let (Some(button), Some(index)) = (button, self.pad(event.which)) {
+                    changes.push(InputChange::GamepadButton(index, button, ButtonChange::Released));
+                }
+            }
+            ControllerAxisMotion(ref event) => {
+                let axis = map_controller_axis(event.axis as u8);
+                if let (Some(axis), Some(index)) = (axis, self.pad(event.which)) {
+                    // SDL axes range from -32768 to 32767.
+                    let value = (event.value as f32 / 32767.0).max(-1.0);
+                    changes.push(InputChange::GamepadAxis(index, axis, value));
+                    self.update_trigger(index, axis, value, &mut changes);
+                }
+            }
+            ControllerDeviceRemoved(ref event) => {
+                self.remove_pad(event.which, &mut changes);
+            }
+            TextInput(_) => {
+                self.composing = false;
+                event.kind.describe_changes(|change| changes.push(change));
+            }
+            TextEditing(ref editing) => {
+                if editing.text.is_empty() {
+                    self.composing = false;
+                } else if !self.composing {
+                    self.composing = true;
+                    changes.push(TextChange::CompositionStart.into());
+                }
+                event.kind.describe_changes(|change| changes.push(change));
+            }
+            ref kind => kind.describe_changes(|change| changes.push(change)),
+        }
+        changes
+    }
+}
+
+impl Default for Rsdl2Events {
+    fn default() -> Rsdl2Events {
+        Rsdl2Events::new()
+    }
+}
+
+/// Describes the movement of one wheel axis, given the directions of its
+/// positive and negative values.
+fn wheel_changes<F: FnMut(InputChange)>(amount: i32,
+                                        positive: WheelDirection,
+                                        negative: WheelDirection,
+                                        handler: &mut F) {
+    if amount > 0 {
+        handler(InputChange::Wheel(positive, amount as f32));
+    } else if amount < 0 {
+        handler(InputChange::Wheel(negative, -amount as f32));
+    }
+}
+
+/// Converts a range of characters in the text (as SDL counts them) to a
+/// range of bytes.
+fn byte_range(text: &str, start: usize, length: usize) -> ::std::ops::Range<usize> {
+    let offset = |chars: usize| text.char_indices().nth(chars).map_or(text.len(), |(index, _)| index);
+    offset(start)..offset(start + length)
+}
+
 impl DescribeInputChanges for EventKind {
     fn describe_changes<F: FnMut(InputChange)>(&self, mut handler: F) {
         use rsdl2::events::EventKind::*;
@@ -69,6 +554,33 @@ impl DescribeInputChanges for EventKind {
                 handler(key_change(keysym, ButtonChange::Released));
                 handler(scan_change(keysym, ButtonChange::Released));
             }
+            MouseButtonDown(ref mouse) => {
+                let button = MouseButton::from_number(mouse.button as u8);
+                handler(InputChange::MouseButton(button, ButtonChange::Pressed));
+            }
+            MouseButtonUp(ref mouse) => {
+                let button = MouseButton::from_number(mouse.button as u8);
+                handler(InputChange::MouseButton(button, ButtonChange::Released));
+            }
+            MouseWheel(ref wheel) => {
+                wheel_changes(wheel.y, WheelDirection::Up, WheelDirection::Down, &mut handler);
+                wheel_changes(wheel.x, WheelDirection::Right, WheelDirection::Left, &mut handler);
+            }
+            TextInput(ref input) => {
+                handler(TextChange::Commit(input.text.clone()).into());
+            }
+            TextEditing(ref editing) => {
+                if editing.text.is_empty() {
+                    handler(TextChange::CompositionEnd.into());
+                } else {
+                    let cursor = byte_range(&editing.text, editing.start as usize, editing.length as usize);
+                    handler(TextChange::CompositionUpdate {
+                            text: editing.text.clone(),
+                            cursor,
+                        }
+                        .into());
+                }
+            }
             Quit => {
                 handler(Signal::QuitRequest.into());
             }
@@ -0,0 +1,206 @@
+//! Timed input sequences (ie: fighting game motions and combos).
+
+use std::time::{Duration, Instant};
+use super::change::{ButtonChange, InputChange};
+use super::description::InputDesc;
+use super::map::ButtonUpdateSource;
+
+/// How long a step of a sequence may follow the previous step.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Window {
+    /// The step must happen within this many frames of the previous step.
+    ///
+    /// Frames are counted by calling `advance_frame` on the `InputMap`.
+    Frames(u32),
+    /// The step must happen within this duration of the previous step.
+    Time(Duration),
+}
+
+impl Window {
+    /// Returns whether something that happened `frames` frames or `elapsed`
+    /// time ago is still within this window.
+    pub fn contains(&self, frames: u64, elapsed: Duration) -> bool {
+        match *self {
+            Window::Frames(max_frames) => frames <= max_frames as u64,
+            Window::Time(duration) => elapsed <= duration,
+        }
+    }
+}
+
+/// A single step of a sequence.
+#[derive(Debug, Clone)]
+struct Step {
+    /// Indices of the sources that must be held for the step.
+    inputs: Vec<usize>,
+    /// The window within which the step must follow the previous one.
+    window: Window,
+}
+
+/// An ordered list of inputs which triggers an action when entered in time.
+///
+/// Each step is one or more button-type sources. A step is entered when the
+/// last of its sources is pressed while the others are held, so
+/// 'down, down + forward, forward + punch' can be described as three steps.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate dalgi;
+/// # use dalgi::input::*;
+/// # input! {
+/// #     pub struct Input {
+/// #         [button]
+/// #         pub struct Buttons<ButtonId> { fireball, }
+/// #         [signal]
+/// #         pub struct Signals<SignalId> { }
+/// #     }
+/// # }
+/// # fn main() {
+/// let fireball = Sequence::new(Window::Frames(10))
+///     .then(Key::Down)
+///     .then_all(vec![Key::Down, Key::Right])
+///     .then_all(vec![Key::Right, Key::J]);
+/// let mut map = InputMap::new();
+/// map.add_button_sequence(ButtonId::fireball, fireball);
+///
+/// let mut input = Input::new();
+/// let key = |key, change| InputChange::Key(KeyDesc::new(key), change);
+/// map.apply(&key(Key::Down, ButtonChange::Pressed), &mut input);
+/// map.apply(&key(Key::Right, ButtonChange::Pressed), &mut input);
+/// map.apply(&key(Key::Down, ButtonChange::Released), &mut input);
+/// map.apply(&key(Key::J, ButtonChange::Pressed), &mut input);
+/// assert!(input.button.fireball.pressed);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    window: Window,
+    sources: Vec<InputDesc>,
+    steps: Vec<Step>,
+    held: Vec<bool>,
+    /// How many steps have been entered.
+    progress: usize,
+    /// The frame and time at which the last step was entered.
+    last_step: Option<(u64, Instant)>,
+}
+
+impl Sequence {
+    /// Creates a new empty sequence, whose steps must each follow the
+    /// previous step within the given window.
+    pub fn new(window: Window) -> Sequence {
+        Sequence {
+            window,
+            sources: Vec::new(),
+            steps: Vec::new(),
+            held: Vec::new(),
+            progress: 0,
+            last_step: None,
+        }
+    }
+
+    /// Builder method to add a step of a single source.
+    pub fn then<D: Into<ButtonUpdateSource>>(self, source: D) -> Sequence {
+        self.then_all(vec![source])
+    }
+
+    /// Builder method to add a step of several sources held together.
+    pub fn then_all<D: Into<ButtonUpdateSource>>(self, sources: Vec<D>) -> Sequence {
+        let window = self.window;
+        self.then_all_within(window, sources)
+    }
+
+    /// Builder method to add a step of several sources held together, which
+    /// must follow the previous step within the given window.
+    pub fn then_all_within<D>(mut self, window: Window, sources: Vec<D>) -> Sequence
+        where D: Into<ButtonUpdateSource>
+    {
+        let mut inputs = Vec::new();
+        for source in sources {
+            let desc: InputDesc = source.into().into();
+            let index = match self.sources.iter().position(|s| *s == desc) {
+                Some(index) => index,
+                None => {
+                    self.sources.push(desc);
+                    self.held.push(false);
+                    self.sources.len() - 1
+                }
+            };
+            inputs.push(index);
+        }
+        self.steps.push(Step {
+            inputs,
+            window,
+        });
+        self
+    }
+
+    /// Returns the number of steps in this sequence.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns whether this sequence has no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns how many steps of the sequence have currently been entered.
+    pub fn progress(&self) -> usize {
+        self.progress
+    }
+
+    /// Forgets the entered steps if the next step didn't arrive in time.
+    pub fn expire(&mut self, frame: u64, now: Instant) {
+        if let Some((last_frame, last_time)) = self.last_step {
+            let window = self.steps[self.progress].window;
+            if !window.contains(frame - last_frame, now.duration_since(last_time)) {
+                self.progress = 0;
+                self.last_step = None;
+            }
+        }
+    }
+
+    /// Updates the sequence from the given change.
+    /// Returns whether this change completed the sequence.
+    pub fn update(&mut self, change: &InputChange, frame: u64, now: Instant) -> bool {
+        let mut pressed = None;
+        for (index, source) in self.sources.iter().enumerate() {
+            match change.button_change_for(source) {
+                Some(ButtonChange::Pressed) => {
+                    self.held[index] = true;
+                    pressed = Some(index);
+                }
+                Some(ButtonChange::Released) => self.held[index] = false,
+                Some(ButtonChange::Repeated) | None => {}
+            }
+        }
+        let pressed = match pressed {
+            Some(pressed) if !self.steps.is_empty() => pressed,
+            _ => return false,
+        };
+
+        self.expire(frame, now);
+        if self.step_entered(self.progress, pressed) {
+            self.progress += 1;
+        } else if self.step_entered(0, pressed) {
+            // Start over, rather than waiting for the old attempt to expire.
+            self.progress = 1;
+        } else {
+            return false;
+        }
+
+        if self.progress == self.steps.len() {
+            self.progress = 0;
+            self.last_step = None;
+            true
+        } else {
+            self.last_step = Some((frame, now));
+            false
+        }
+    }
+
+    /// Returns whether pressing the given source entered the given step.
+    fn step_entered(&self, step: usize, pressed: usize) -> bool {
+        let inputs = &self.steps[step].inputs;
+        inputs.contains(&pressed) && inputs.iter().all(|&index| self.held[index])
+    }
+}
@@ -6,3 +6,20 @@ pub enum Signal {
     /// The user or OS has requested that the application should close.
     QuitRequest,
 }
+
+impl Signal {
+    /// Returns the name of this signal.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Signal::QuitRequest => "quit_request",
+        }
+    }
+
+    /// Attempts to find a signal with the given name.
+    pub fn from_name(name: &str) -> Option<Signal> {
+        Some(match name {
+            "quit_request" => Signal::QuitRequest,
+            _ => return None,
+        })
+    }
+}
@@ -1,6 +1,9 @@
 //! Functionality to describe an input state.
 
 use std::hash::Hash;
+use std::time::{Duration, Instant};
+use super::mouse::WheelValue;
+use super::text::TextValue;
 
 /// An input value that knows how to change its state in the next game frame.
 pub trait AdvanceFrame {
@@ -18,6 +21,15 @@ pub trait AdvanceFrame {
     ///         self.released = false;
     ///         // The button has not been repeated in the new frame.
     ///         self.repeats = 0;
+    ///         // The button has not been pressed or released in the new frame.
+    ///         self.press_count = 0;
+    ///         self.release_count = 0;
+    ///         // The button has not been double-tapped in the new frame.
+    ///         self.double_pressed = false;
+    ///         // If the button is held, it has been held for another frame.
+    ///         if self.held {
+    ///             self.held_frames += 1;
+    ///         }
     ///     }
     /// }
     /// ```
@@ -30,11 +42,45 @@ impl AdvanceFrame for bool {
     }
 }
 
+impl AdvanceFrame for TextValue {
+    fn advance_frame(&mut self) {
+        self.committed.clear();
+    }
+}
+
+impl AdvanceFrame for WheelValue {
+    fn advance_frame(&mut self) {
+        self.x = 0.0;
+        self.y = 0.0;
+    }
+}
+
 impl AdvanceFrame for ButtonValue {
     fn advance_frame(&mut self) {
         self.pressed = false;
         self.released = false;
         self.repeats = 0;
+        self.press_count = 0;
+        self.release_count = 0;
+        self.double_pressed = false;
+        if self.held {
+            self.held_frames += 1;
+        }
+        if let Some(age) = self.buffered_age {
+            self.buffered_age = if age < self.buffer_frames { Some(age + 1) } else { None };
+        }
+    }
+}
+
+impl AdvanceFrame for AxisValue {
+    fn advance_frame(&mut self) {
+        self.previous = self.value;
+    }
+}
+
+impl AdvanceFrame for VectorValue {
+    fn advance_frame(&mut self) {
+        self.previous = self.value;
     }
 }
 
@@ -43,30 +89,89 @@ pub trait InputIndex: PartialEq + Eq + Hash + Copy {}
 
 impl<T> InputIndex for T where T: PartialEq + Eq + Hash + Copy {}
 
+/// An input index for input states that have no inputs of a category
+/// (ie: a state without any axes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoInputs {}
+
+/// An input index whose values can be listed and have names (ie: to show
+/// them in a settings menu or write them to a bindings file).
+///
+/// The `input!` macro implements this for the ids it generates.
+pub trait NamedIndex: InputIndex + 'static {
+    /// Returns every value of the index, in declaration order.
+    fn all() -> &'static [Self];
+
+    /// Returns the name of this index.
+    fn name(&self) -> &'static str;
+
+    /// Attempts to find the index with the given name.
+    fn from_name(name: &str) -> Option<Self>;
+}
+
+impl NamedIndex for NoInputs {
+    fn all() -> &'static [NoInputs] {
+        &[]
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {}
+    }
+
+    fn from_name(_name: &str) -> Option<NoInputs> {
+        None
+    }
+}
+
 /// Describes a type that can have its state updated by an InputMap.
 /// Such a type can be generated using the `input!` macro.
 pub trait InputState: AdvanceFrame {
     /// Identifies a button-style input.
-    type ButtonId: InputIndex;
+    type ButtonId: NamedIndex;
 
     /// Identifies a signal-style input.
-    type SignalId: InputIndex;
+    type SignalId: NamedIndex;
+
+    /// Identifies an axis-style input.
+    type AxisId: NamedIndex;
+
+    /// Identifies a vector-style input.
+    type VectorId: NamedIndex;
 
     /// Returns the state of the button.
     fn get_button<'a>(&'a mut self, id: &Self::ButtonId) -> &'a mut ButtonValue;
 
     /// Returns the state of the signal.
     fn get_signal<'a>(&'a mut self, id: &Self::SignalId) -> &'a mut bool;
+
+    /// Returns the state of the axis.
+    fn get_axis<'a>(&'a mut self, id: &Self::AxisId) -> &'a mut AxisValue;
+
+    /// Returns the state of the vector.
+    fn get_vector<'a>(&'a mut self, id: &Self::VectorId) -> &'a mut VectorValue;
+
+    /// Returns the accumulated mouse wheel movement of this frame, if the
+    /// state keeps track of it.
+    fn get_wheel(&mut self) -> Option<&mut WheelValue>;
+
+    /// Returns the text entered in this frame, if the state keeps track of it.
+    fn get_text(&mut self) -> Option<&mut TextValue>;
 }
 
 /// The value of a button-type input in a single game frame.
 ///
-/// **Note**: This is slightly lossy, as the number of presses and releases
-/// are not counted.
-///
 /// The button can be both pressed and released in the same frame, so if you
 /// need to know if the button is left pressed or not, check the 'held' member.
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// It always reflects the last press or release.
+///
+/// If the button is pressed several times in one frame (ie: when mashing a
+/// button at a low frame rate), `press_count` and `release_count` tell how
+/// many times.
+///
+/// Equality ignores `held_since` and `released_held_time`, which depend on
+/// the clock, so that the same input gives equal values (compare
+/// `held_frames` and `released_held_frames` instead).
+#[derive(Debug, Clone, Default)]
 pub struct ButtonValue {
     /// Whether the button was pressed down in this frame.
     pub pressed: bool,
@@ -79,6 +184,35 @@ pub struct ButtonValue {
     /// (the button was held down for so long that the OS started sending
     /// 'repeat' events).
     pub repeats: u8,
+    /// How many times the button was pressed this frame.
+    pub press_count: u8,
+    /// How many times the button was released this frame.
+    pub release_count: u8,
+    /// Whether the button was pressed a second time in quick succession in
+    /// this frame.
+    ///
+    /// This is only detected for actions with a double-tap window in the
+    /// input map (see `InputMap::set_double_tap`).
+    pub double_pressed: bool,
+    /// For how many frames the button has been held.
+    ///
+    /// This is 0 in the frame the button is pressed, and counts up every time
+    /// the value is advanced to a new frame while held.
+    pub held_frames: u32,
+    /// When the button was pressed, if it is held and the time is known.
+    pub held_since: Option<Instant>,
+    /// For how many frames the button was held when it was last released.
+    pub released_held_frames: u32,
+    /// For how long the button was held when it was last released, if the
+    /// time is known.
+    pub released_held_time: Option<Duration>,
+    /// How many frames ago the button was pressed, if that press is still
+    /// buffered and hasn't been consumed (see `buffered`).
+    pub buffered_age: Option<u32>,
+    /// For how many frames after a press it stays buffered.
+    ///
+    /// This is set by the input map (see `InputMap::set_buffer`).
+    pub buffer_frames: u32,
 }
 
 impl ButtonValue {
@@ -89,6 +223,267 @@ impl ButtonValue {
             released: false,
             held: false,
             repeats: 0,
+            press_count: 0,
+            release_count: 0,
+            double_pressed: false,
+            held_frames: 0,
+            held_since: None,
+            released_held_frames: 0,
+            released_held_time: None,
+            buffered_age: None,
+            buffer_frames: 0,
         }
     }
+
+    /// Marks the button as pressed down at the given time.
+    ///
+    /// If the button is already held, its hold duration is kept.
+    pub fn press(&mut self, now: Option<Instant>) {
+        self.pressed = true;
+        self.press_count = self.press_count.saturating_add(1);
+        if !self.held {
+            self.held = true;
+            self.held_frames = 0;
+            self.held_since = now;
+        }
+    }
+
+    /// Marks the button as released at the given time, and stores for how
+    /// long it was held.
+    pub fn release(&mut self, now: Option<Instant>) {
+        self.released = true;
+        self.release_count = self.release_count.saturating_add(1);
+        if self.held {
+            self.held = false;
+            self.released_held_frames = self.held_frames;
+            self.released_held_time = match (self.held_since, now) {
+                (Some(since), Some(now)) => Some(now.duration_since(since)),
+                _ => None,
+            };
+            self.held_frames = 0;
+            self.held_since = None;
+        }
+    }
+
+    /// Marks the button as pressed and released in an instant (ie: by a
+    /// notch of the mouse wheel), without changing whether it is held.
+    pub fn tap(&mut self) {
+        self.pressed = true;
+        self.released = true;
+        self.press_count = self.press_count.saturating_add(1);
+        self.release_count = self.release_count.saturating_add(1);
+    }
+
+    /// Buffers a press for the given number of frames (ie: to let a jump
+    /// pressed just before landing count when landing).
+    pub fn buffer(&mut self, frames: u32) {
+        self.buffered_age = Some(0);
+        self.buffer_frames = frames;
+    }
+
+    /// Returns whether the button was pressed within its buffer window, and
+    /// that press hasn't been consumed yet.
+    pub fn buffered(&self) -> bool {
+        self.buffered_age.is_some()
+    }
+
+    /// Consumes the buffered press, if any, so that it isn't handled twice.
+    /// Returns whether there was a buffered press.
+    pub fn consume(&mut self) -> bool {
+        self.buffered_age.take().is_some()
+    }
+
+    /// Returns for how long the button has been held at the given time
+    /// (ie: `InputMap::now`), if it is held and the time is known.
+    pub fn held_time(&self, now: Instant) -> Option<Duration> {
+        self.held_since.map(|since| now.duration_since(since))
+    }
+}
+
+impl PartialEq for ButtonValue {
+    fn eq(&self, other: &ButtonValue) -> bool {
+        self.pressed == other.pressed && self.held == other.held && self.released == other.released &&
+        self.repeats == other.repeats && self.press_count == other.press_count &&
+        self.release_count == other.release_count &&
+        self.double_pressed == other.double_pressed && self.held_frames == other.held_frames &&
+        self.released_held_frames == other.released_held_frames &&
+        self.buffered_age == other.buffered_age && self.buffer_frames == other.buffer_frames
+    }
+}
+
+impl Eq for ButtonValue {}
+
+/// The value of an axis-type input (ie: a trigger or a stick direction).
+///
+/// Unlike buttons, axes keep their value across frames, since analog inputs
+/// only report when they change.
+///
+/// Values are compared bit for bit, so that input states can be `Eq`.
+#[derive(Debug, Clone, Default)]
+pub struct AxisValue {
+    /// The value of the axis in this frame.
+    pub value: f32,
+    /// The value of the axis in the previous frame.
+    pub previous: f32,
+}
+
+impl AxisValue {
+    /// Creates a new axis value at rest.
+    pub fn new() -> AxisValue {
+        AxisValue {
+            value: 0.0,
+            previous: 0.0,
+        }
+    }
+
+    /// Returns how much the value has changed since the previous frame.
+    pub fn delta(&self) -> f32 {
+        self.value - self.previous
+    }
+}
+
+/// The value of a two-dimensional input (ie: a movement direction).
+///
+/// Positive values are to the right and down, like the gamepad sticks.
+/// Like axes, vectors keep their value across frames.
+///
+/// Values are compared bit for bit, so that input states can be `Eq`.
+#[derive(Debug, Clone, Default)]
+pub struct VectorValue {
+    /// The `(x, y)` value of the vector in this frame.
+    pub value: (f32, f32),
+    /// The `(x, y)` value of the vector in the previous frame.
+    pub previous: (f32, f32),
+}
+
+impl VectorValue {
+    /// Creates a new vector value at rest.
+    pub fn new() -> VectorValue {
+        VectorValue {
+            value: (0.0, 0.0),
+            previous: (0.0, 0.0),
+        }
+    }
+
+    /// Returns the length of the vector in this frame.
+    pub fn length(&self) -> f32 {
+        let (x, y) = self.value;
+        (x * x + y * y).sqrt()
+    }
+
+    /// Returns how much the value has changed since the previous frame.
+    pub fn delta(&self) -> (f32, f32) {
+        (self.value.0 - self.previous.0, self.value.1 - self.previous.1)
+    }
+}
+
+impl PartialEq for AxisValue {
+    fn eq(&self, other: &AxisValue) -> bool {
+        same_bits(self.value, other.value) && same_bits(self.previous, other.previous)
+    }
+}
+
+impl Eq for AxisValue {}
+
+impl PartialEq for VectorValue {
+    fn eq(&self, other: &VectorValue) -> bool {
+        same_bits(self.value.0, other.value.0) && same_bits(self.value.1, other.value.1) &&
+        same_bits(self.previous.0, other.previous.0) &&
+        same_bits(self.previous.1, other.previous.1)
+    }
+}
+
+impl Eq for VectorValue {}
+
+/// Compares two analog values bit for bit, so that they can be `Eq`.
+pub(crate) fn same_bits(a: f32, b: f32) -> bool {
+    a.to_bits() == b.to_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::{AdvanceFrame, ButtonValue};
+
+    /// Mashes a button `presses` times, with `events_per_frame` presses and
+    /// releases handled between each frame, and returns the value of every
+    /// frame.
+    fn mash(presses: usize, events_per_frame: usize) -> Vec<ButtonValue> {
+        let mut button = ButtonValue::new();
+        let mut frames = Vec::new();
+        for event in 0..presses * 2 {
+            if event % 2 == 0 {
+                button.press(None);
+            } else {
+                button.release(None);
+            }
+            if (event + 1) % events_per_frame == 0 {
+                frames.push(button.clone());
+                button.advance_frame();
+            }
+        }
+        frames.push(button);
+        frames
+    }
+
+    #[test]
+    fn mashing_counts_every_press_and_release() {
+        for &events_per_frame in &[1, 2, 3, 4, 7, 20] {
+            let frames = mash(10, events_per_frame);
+            let presses: usize = frames.iter().map(|f| f.press_count as usize).sum();
+            let releases: usize = frames.iter().map(|f| f.release_count as usize).sum();
+            assert_eq!(presses, 10, "{} events per frame", events_per_frame);
+            assert_eq!(releases, 10, "{} events per frame", events_per_frame);
+        }
+    }
+
+    #[test]
+    fn held_reflects_last_transition() {
+        // Three events per frame: press, release, press | release, press, release | ...
+        let frames = mash(3, 3);
+        assert_eq!(frames[0].press_count, 2);
+        assert_eq!(frames[0].release_count, 1);
+        assert!(frames[0].pressed && frames[0].released && frames[0].held);
+        assert_eq!(frames[1].press_count, 1);
+        assert_eq!(frames[1].release_count, 2);
+        assert!(frames[1].pressed && frames[1].released && !frames[1].held);
+    }
+
+    #[test]
+    fn counts_reset_on_new_frame() {
+        let mut button = ButtonValue::new();
+        button.press(None);
+        button.release(None);
+        button.press(None);
+        button.advance_frame();
+        assert_eq!(button.press_count, 0);
+        assert_eq!(button.release_count, 0);
+        assert!(!button.pressed && !button.released && button.held);
+    }
+
+    #[test]
+    fn tap_counts_without_changing_held() {
+        let mut button = ButtonValue::new();
+        button.tap();
+        button.tap();
+        assert_eq!(button.press_count, 2);
+        assert_eq!(button.release_count, 2);
+        assert!(!button.held);
+    }
+
+    #[test]
+    fn equality_ignores_the_clock() {
+        let start = Instant::now();
+        let mut early = ButtonValue::new();
+        let mut late = ButtonValue::new();
+        early.press(Some(start));
+        late.press(Some(start + Duration::from_secs(1)));
+        assert_eq!(early, late);
+        assert_eq!(early.held_time(start + Duration::from_secs(2)), Some(Duration::from_secs(2)));
+
+        early.release(Some(start + Duration::from_secs(3)));
+        late.release(Some(start + Duration::from_secs(5)));
+        assert_eq!(early, late);
+        assert_eq!(early.released_held_time, Some(Duration::from_secs(3)));
+    }
 }
@@ -0,0 +1,66 @@
+//! Text entry and IME composition.
+
+use std::ops::Range;
+
+/// A change to the text being entered (ie: in a chat box).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextChange {
+    /// Text was entered, either directly or by finishing a composition.
+    Commit(String),
+    /// The IME started composing text.
+    CompositionStart,
+    /// The text being composed by the IME changed.
+    CompositionUpdate {
+        /// The text being composed.
+        text: String,
+        /// The byte range of the cursor (or selection) within the text.
+        cursor: Range<usize>,
+    },
+    /// The IME stopped composing text (the text is committed separately).
+    CompositionEnd,
+}
+
+/// Text that is still being composed by an IME.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Composition {
+    /// The text being composed.
+    pub text: String,
+    /// The byte range of the cursor (or selection) within the text.
+    pub cursor: Range<usize>,
+}
+
+/// The text entered in a single game frame.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TextValue {
+    /// The text committed in this frame.
+    pub committed: String,
+    /// The ongoing IME composition, if any.
+    ///
+    /// Unlike the committed text, this is kept across frames.
+    pub composition: Option<Composition>,
+}
+
+impl TextValue {
+    /// Creates a new empty text value.
+    pub fn new() -> TextValue {
+        TextValue {
+            committed: String::new(),
+            composition: None,
+        }
+    }
+
+    /// Updates the text from the given change.
+    pub fn apply(&mut self, change: &TextChange) {
+        match *change {
+            TextChange::Commit(ref text) => self.committed.push_str(text),
+            TextChange::CompositionStart => self.composition = Some(Composition::default()),
+            TextChange::CompositionUpdate { ref text, ref cursor } => {
+                self.composition = Some(Composition {
+                    text: text.clone(),
+                    cursor: cursor.clone(),
+                });
+            }
+            TextChange::CompositionEnd => self.composition = None,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+#[macro_use]
+extern crate dalgi;
+use dalgi::input::*;
+
+input! {
+    pub struct Input {
+        [button]
+        pub struct Buttons<ButtonId> {
+            jump,
+            crouch,
+            menu,
+        }
+
+        [signal]
+        pub struct Signals<SignalId> {
+            quit,
+        }
+
+        [axis]
+        pub struct Axes<AxisId> {
+            throttle,
+        }
+    }
+}
+
+fn default_map() -> InputMap<ButtonId, SignalId, AxisId> {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::jump, Key::Space);
+    map.add_button(ButtonId::jump, GamepadButton::South);
+    map.add_button(ButtonId::crouch, Key::Other(1073742106));
+    map.add_button(ButtonId::crouch,
+                   ButtonUpdateSource::Chord(vec![KeyDesc::new(Key::Q), KeyDesc::new(Key::E).scancode()]));
+    map.add_button(ButtonId::menu, KeyDesc::new(Key::M).mods(Mods::CTRL));
+    map.add_signal(SignalId::quit, Signal::QuitRequest);
+    map.add_axis(AxisId::throttle, AxisUpdateSource::GamepadAxis(1, GamepadAxis::RightTrigger));
+    map
+}
+
+fn button_names(map: &InputMap<ButtonId, SignalId, AxisId>, id: ButtonId) -> Vec<String> {
+    let mut names: Vec<String> = map.button_sources(id)
+        .into_iter()
+        .map(|source| Into::<InputDesc>::into(source).to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn maps_round_trip_through_files() {
+    let map = default_map();
+    let text = Bindings::from_map(&map).to_string();
+
+    let mut read: InputMap<ButtonId, SignalId, AxisId> = InputMap::new();
+    Bindings::parse(&text).unwrap().read_into(&mut read).unwrap();
+    for &id in ButtonId::ALL {
+        assert_eq!(button_names(&read, id), button_names(&map, id));
+    }
+    assert_eq!(read.signal_sources(SignalId::quit).len(), 1);
+    assert_eq!(read.axis_sources(AxisId::throttle).len(), 1);
+
+    assert_eq!(Bindings::from_map(&read).to_string(), text);
+}
+
+#[test]
+fn updates_keep_comments_and_order() {
+    let text = "\
+[button]  # Keyboard first.
+jump = [w], space  # Both work.
+";
+    let mut bindings = Bindings::parse(text).unwrap();
+    let mut map = default_map();
+    map.remove_button(ButtonId::jump, GamepadButton::South);
+    bindings.update_from(&map);
+    let updated = bindings.to_string();
+    assert!(updated.starts_with("[button]  # Keyboard first.\njump = space  # Both work.\n"));
+    assert!(updated.contains("crouch = "));
+    assert!(updated.contains("[signal]\nquit = signal:quit_request\n"));
+}
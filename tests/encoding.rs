@@ -0,0 +1,129 @@
+#[macro_use]
+extern crate dalgi;
+use dalgi::input::*;
+
+input! {
+    pub struct Input {
+        [button]
+        pub struct Buttons<ButtonId> {
+            jump,
+            fire,
+        }
+
+        [signal]
+        pub struct Signals<SignalId> {
+            quit,
+        }
+
+        [axis]
+        pub struct Axes<AxisId> {
+            throttle,
+        }
+
+        [vector]
+        pub struct Vectors<VectorId> {
+            movement,
+        }
+    }
+}
+
+mod other {
+    input! {
+        pub struct Input {
+            [button]
+            pub struct Buttons<ButtonId> {
+                jump,
+            }
+
+            [signal]
+            pub struct Signals<SignalId> {
+                quit,
+            }
+        }
+    }
+}
+
+mod renamed {
+    input! {
+        pub struct Input {
+            [button]
+            pub struct Buttons<ButtonId> {
+                fire,
+                jump,
+            }
+
+            [signal]
+            pub struct Signals<SignalId> {
+                quit,
+            }
+
+            [axis]
+            pub struct Axes<AxisId> {
+                throttle,
+            }
+
+            [vector]
+            pub struct Vectors<VectorId> {
+                movement,
+            }
+        }
+    }
+}
+
+fn busy() -> Input {
+    let mut input = Input::new();
+    input.button.jump.press(None);
+    input.button.jump.release(None);
+    input.button.jump.press(None);
+    input.button.jump.repeats = 3;
+    input.button.fire.double_pressed = true;
+    input.signal.quit = true;
+    input.axis.throttle.value = -0.25;
+    input.vector.movement.value = (0.5, 1.0);
+    input
+}
+
+#[test]
+fn states_round_trip() {
+    let input = busy();
+    let mut decoded = Input::new();
+    decoded.decode(&input.encode()).unwrap();
+    assert_eq!(decoded, input);
+
+    let mut empty = busy();
+    empty.decode(&Input::new().encode()).unwrap();
+    assert_eq!(empty, Input::new());
+}
+
+#[test]
+fn deltas_round_trip() {
+    let previous = busy();
+    let mut input = previous.clone();
+    input.advance_frame();
+    input.axis.throttle.value = 0.75;
+
+    let delta = input.encode_delta(&previous);
+    assert_eq!(delta.len(), 7);
+    let mut decoded = Input::new();
+    decoded.decode_delta(&previous, &delta).unwrap();
+    assert_eq!(decoded.encode(), input.encode());
+    assert!(decoded.button.jump.held && !decoded.button.jump.pressed);
+    assert_eq!(decoded.axis.throttle.value, 0.75);
+    assert_eq!(decoded.vector.movement.value, (0.5, 1.0));
+}
+
+#[test]
+fn mismatched_data_is_rejected() {
+    let bytes = busy().encode();
+    let mut input = Input::new();
+    assert_eq!(input.decode(&bytes[..bytes.len() - 1]), Err(DecodeError));
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert_eq!(input.decode(&longer), Err(DecodeError));
+
+    let mut other = other::Input::new();
+    assert_eq!(other.decode(&bytes), Err(DecodeError));
+    assert_eq!(input.decode(&other::Input::new().encode()), Err(DecodeError));
+    let mut renamed = renamed::Input::new();
+    assert_eq!(renamed.decode(&bytes), Err(DecodeError));
+}
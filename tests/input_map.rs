@@ -0,0 +1,679 @@
+#[macro_use]
+extern crate dalgi;
+use dalgi::input::*;
+
+input! {
+    pub struct Input {
+        [button]
+        pub struct Buttons<ButtonId> {
+            walk,
+            sprint,
+            save,
+            save_as,
+            zoom,
+        }
+
+        [signal]
+        pub struct Signals<SignalId> {
+            quit,
+        }
+
+        [axis]
+        pub struct Axes<AxisId> {
+            strafe,
+        }
+
+        [vector]
+        pub struct Vectors<VectorId> {
+            movement,
+        }
+
+        [wheel]
+        pub wheel,
+
+        [text]
+        pub text,
+    }
+}
+
+fn key(desc: KeyDesc, change: ButtonChange) -> InputChange {
+    InputChange::Key(desc, change)
+}
+
+#[test]
+fn unmodified_bindings_trigger_with_modifiers_held() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::walk, KeyDesc::new(Key::W).scancode());
+    map.add_button(ButtonId::sprint, Key::LeftShift);
+    map.add_virtual_axis(AxisId::strafe, Key::A, Key::D, BothHeld::Cancel);
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::LeftShift).mods(Mods::LEFT_SHIFT), ButtonChange::Pressed),
+              &mut input);
+    map.apply(&key(KeyDesc::new(Key::W).scancode().mods(Mods::LEFT_SHIFT), ButtonChange::Pressed),
+              &mut input);
+    map.apply(&key(KeyDesc::new(Key::D).mods(Mods::LEFT_SHIFT), ButtonChange::Pressed),
+              &mut input);
+    assert!(input.button.sprint.held);
+    assert!(input.button.walk.held);
+    assert_eq!(input.axis.strafe.value, 1.0);
+
+    map.apply(&key(KeyDesc::new(Key::W).scancode().mods(Mods::LEFT_SHIFT), ButtonChange::Released),
+              &mut input);
+    assert!(!input.button.walk.held);
+}
+
+#[test]
+fn modified_bindings_need_exactly_their_modifiers() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::save, KeyDesc::new(Key::S).mods(Mods::CTRL));
+    map.add_button(ButtonId::save_as, KeyDesc::new(Key::S).mods(Mods::CTRL | Mods::SHIFT));
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::S).mods(Mods::LEFT_CTRL), ButtonChange::Pressed),
+              &mut input);
+    assert!(input.button.save.pressed);
+    assert!(!input.button.save_as.pressed);
+    map.apply(&key(KeyDesc::new(Key::S), ButtonChange::Released), &mut input);
+    assert!(!input.button.save.held);
+
+    input.advance_frame();
+    let ctrl_shift = Mods::LEFT_CTRL | Mods::RIGHT_SHIFT;
+    map.apply(&key(KeyDesc::new(Key::S).mods(ctrl_shift), ButtonChange::Pressed), &mut input);
+    assert!(!input.button.save.pressed);
+    assert!(input.button.save_as.pressed);
+
+    input.advance_frame();
+    map.apply(&key(KeyDesc::new(Key::S), ButtonChange::Released), &mut input);
+    map.apply(&key(KeyDesc::new(Key::S), ButtonChange::Pressed), &mut input);
+    assert!(!input.button.save.pressed);
+    assert!(!input.button.save_as.pressed);
+}
+
+#[test]
+fn every_wheel_notch_presses_once() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::zoom, WheelDirection::Up);
+    let mut input = Input::new();
+
+    map.apply(&InputChange::Wheel(WheelDirection::Up, 3.0), &mut input);
+    assert_eq!(input.button.zoom.press_count, 3);
+    assert_eq!(input.wheel.y, 3.0);
+
+    input.advance_frame();
+    map.apply(&InputChange::Wheel(WheelDirection::Up, 0.5), &mut input);
+    assert!(!input.button.zoom.pressed);
+    map.apply(&InputChange::Wheel(WheelDirection::Up, 0.75), &mut input);
+    assert_eq!(input.button.zoom.press_count, 1);
+    assert_eq!(input.wheel.y, 1.25);
+
+    input.advance_frame();
+    map.apply(&InputChange::Wheel(WheelDirection::Down, 0.5), &mut input);
+    map.apply(&InputChange::Wheel(WheelDirection::Up, 0.5), &mut input);
+    assert!(!input.button.zoom.pressed);
+    assert_eq!(input.wheel.y, 0.0);
+}
+
+#[test]
+fn generated_states_are_eq() {
+    fn assert_eq<T: Eq>() {}
+    assert_eq::<Input>();
+    assert_eq!(Input::new(), Input::new());
+}
+
+fn stick(axis: GamepadAxis, value: f32) -> InputChange {
+    InputChange::GamepadAxis(0, axis, value)
+}
+
+#[test]
+fn axis_sources_combine() {
+    let mut map = InputMap::new();
+    map.add_axis(AxisId::strafe, GamepadAxis::LeftX);
+    map.add_axis(AxisId::strafe, GamepadAxis::RightX);
+    map.add_virtual_axis(AxisId::strafe, Key::A, Key::D, BothHeld::Cancel);
+    let mut input = Input::new();
+
+    map.apply(&stick(GamepadAxis::LeftX, 0.25), &mut input);
+    map.apply(&stick(GamepadAxis::RightX, 0.5), &mut input);
+    assert_eq!(input.axis.strafe.value, 0.75);
+
+    map.apply(&stick(GamepadAxis::LeftX, 0.0), &mut input);
+    assert_eq!(input.axis.strafe.value, 0.5);
+
+    map.apply(&key(KeyDesc::new(Key::D), ButtonChange::Pressed), &mut input);
+    assert_eq!(input.axis.strafe.value, 1.0);
+    map.apply(&key(KeyDesc::new(Key::D), ButtonChange::Released), &mut input);
+    assert_eq!(input.axis.strafe.value, 0.5);
+}
+
+#[test]
+fn deadzones_scale_the_rest_of_the_range() {
+    let mut map = InputMap::new();
+    map.add_axis(AxisId::strafe, GamepadAxis::LeftX);
+    map.set_deadzone(GamepadAxis::LeftX, 0.2);
+    let mut input = Input::new();
+
+    map.apply(&stick(GamepadAxis::LeftX, -0.15), &mut input);
+    assert_eq!(input.axis.strafe.value, 0.0);
+    map.apply(&stick(GamepadAxis::LeftX, 0.6), &mut input);
+    assert!((input.axis.strafe.value - 0.5).abs() < 1e-6);
+    map.apply(&stick(GamepadAxis::LeftX, -1.0), &mut input);
+    assert_eq!(input.axis.strafe.value, -1.0);
+}
+
+#[test]
+fn combined_vectors_keep_a_length_of_one() {
+    let mut map = InputMap::new();
+    map.add_vector_buttons(VectorId::movement, Key::W, Key::S, Key::A, Key::D, true);
+    map.add_vector_axes(VectorId::movement, GamepadAxis::LeftX, GamepadAxis::LeftY, true);
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    map.apply(&key(KeyDesc::new(Key::D), ButtonChange::Pressed), &mut input);
+    map.apply(&stick(GamepadAxis::LeftX, 0.7), &mut input);
+    map.apply(&stick(GamepadAxis::LeftY, -0.7), &mut input);
+    assert!((input.vector.movement.length() - 1.0).abs() < 1e-6);
+    let (x, y) = input.vector.movement.value;
+    assert!((x + y).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic]
+fn chords_with_modifier_keys_are_rejected() {
+    let mut map: InputMap<ButtonId, SignalId> = InputMap::new();
+    map.add_button(ButtonId::save, ButtonUpdateSource::Chord(vec![KeyDesc::new(Key::LeftCtrl),
+                                                                  KeyDesc::new(Key::S)]));
+}
+
+#[test]
+fn sequence_windows_follow_the_frame_clock() {
+    use std::time::Duration;
+    let mut map = InputMap::new();
+    map.set_frame_time(Some(Duration::from_millis(10)));
+    let sequence = Sequence::new(Window::Time(Duration::from_millis(25)))
+        .then(Key::Down)
+        .then(Key::J);
+    map.add_button_sequence(ButtonId::save, sequence);
+    assert!(map.bound_buttons().contains(&ButtonId::save));
+    assert_eq!(map.button_sequences(ButtonId::save).len(), 1);
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::Down), ButtonChange::Pressed), &mut input);
+    for _ in 0..2 {
+        map.advance_frame();
+        input.advance_frame();
+    }
+    map.apply(&key(KeyDesc::new(Key::J), ButtonChange::Pressed), &mut input);
+    assert!(input.button.save.pressed);
+
+    map.apply(&key(KeyDesc::new(Key::Down), ButtonChange::Pressed), &mut input);
+    for _ in 0..3 {
+        map.advance_frame();
+        input.advance_frame();
+    }
+    map.apply(&key(KeyDesc::new(Key::J), ButtonChange::Pressed), &mut input);
+    assert!(!input.button.save.pressed);
+}
+
+/// A key event as backends send it: one change for the keycode and one for
+/// the scancode.
+struct KeyEvent(Key, ButtonChange);
+
+impl DescribeInputChanges for KeyEvent {
+    fn describe_changes<F: FnMut(InputChange)>(&self, mut handler: F) {
+        handler(key(KeyDesc::new(self.0), self.1));
+        handler(key(KeyDesc::new(self.0).scancode(), self.1));
+    }
+}
+
+#[test]
+fn double_taps_count_physical_presses() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::walk, Key::A);
+    map.add_button(ButtonId::walk, KeyDesc::new(Key::A).scancode());
+    map.add_button(ButtonId::walk, Key::J);
+    map.add_button_sequence(ButtonId::walk, Sequence::new(Window::Frames(10)).then(Key::Down).then(Key::J));
+    map.set_double_tap(ButtonId::walk, Window::Frames(10));
+    let mut input = Input::new();
+
+    map.apply(&KeyEvent(Key::A, ButtonChange::Pressed), &mut input);
+    assert!(input.button.walk.pressed);
+    assert!(!input.button.walk.double_pressed);
+    map.apply(&KeyEvent(Key::A, ButtonChange::Released), &mut input);
+    assert!(!input.button.walk.held);
+    map.apply(&KeyEvent(Key::A, ButtonChange::Pressed), &mut input);
+    assert!(input.button.walk.double_pressed);
+    map.apply(&KeyEvent(Key::A, ButtonChange::Released), &mut input);
+
+    // A key that both presses the button and completes a sequence for it.
+    for _ in 0..20 {
+        map.advance_frame();
+        input.advance_frame();
+    }
+    map.apply(&KeyEvent(Key::Down, ButtonChange::Pressed), &mut input);
+    map.apply(&KeyEvent(Key::J, ButtonChange::Pressed), &mut input);
+    assert!(input.button.walk.pressed);
+    assert!(!input.button.walk.double_pressed);
+}
+
+#[test]
+fn text_is_added_to_the_state() {
+    let mut map: InputMap<ButtonId, SignalId, AxisId, VectorId> = InputMap::new();
+    let mut input = Input::new();
+
+    map.apply(&TextChange::Commit("안".to_string()), &mut input);
+    map.apply(&TextChange::CompositionUpdate {
+                   text: "녕".to_string(),
+                   cursor: 3..3,
+               },
+              &mut input);
+    assert_eq!(input.text.committed, "안");
+    assert_eq!(input.text.composition.as_ref().map(|c| &c.text[..]), Some("녕"));
+
+    input.advance_frame();
+    assert_eq!(input.text.committed, "");
+    assert!(input.text.composition.is_some());
+}
+
+#[test]
+fn quit_and_axes_pass_through_captures() {
+    let mut map = InputMap::new();
+    map.add_signal(SignalId::quit, Signal::QuitRequest);
+    map.add_axis(AxisId::strafe, GamepadAxis::LeftX);
+    let mut input = Input::new();
+
+    for &capture in &[Capture::button(), Capture::signal()] {
+        map.capture(capture);
+        map.apply(&InputChange::Signal(Signal::QuitRequest), &mut input);
+        map.apply(&stick(GamepadAxis::LeftX, 0.5), &mut input);
+        assert!(input.signal.quit);
+        assert_eq!(input.axis.strafe.value, 0.5);
+        assert_eq!(map.take_captured(), None);
+
+        map.apply(&key(KeyDesc::new(Key::Q), ButtonChange::Pressed), &mut input);
+        assert!(map.take_captured().is_some());
+        input = Input::new();
+    }
+}
+
+#[test]
+fn conflicts_cover_everything_one_press_triggers() {
+    let mut map: InputMap<ButtonId, SignalId, AxisId, VectorId> = InputMap::new();
+    map.add_button(ButtonId::walk, Key::S);
+    map.add_button(ButtonId::sprint, KeyDesc::new(Key::S).scancode());
+    map.add_button(ButtonId::save, KeyDesc::new(Key::S).mods(Mods::CTRL));
+    map.add_button(ButtonId::save_as, KeyDesc::new(Key::S).mods(Mods::CTRL | Mods::SHIFT));
+    map.add_button(ButtonId::zoom, Key::Z);
+
+    let conflicts = map.conflicts();
+    let sources: Vec<String> = conflicts.iter().map(|c| c.source.to_string()).collect();
+    assert_eq!(sources, vec!["[s]", "ctrl+s", "s", "shift+ctrl+s"]);
+    let ctrl_s = &conflicts[1];
+    assert_eq!(ctrl_s.actions.len(), 3);
+    assert!(!ctrl_s.actions.contains(&ActionId::Button(ButtonId::save_as)));
+
+    map.set_group(ActionId::Button(ButtonId::walk), "gameplay");
+    map.set_group(ActionId::Button(ButtonId::sprint), "gameplay");
+    map.set_group(ActionId::Button(ButtonId::save), "menu");
+    map.set_group(ActionId::Button(ButtonId::save_as), "menu");
+    map.allow_sharing("gameplay", "menu");
+    map.allow_sharing("gameplay", "gameplay");
+    assert_eq!(map.conflicts(), vec![]);
+}
+
+#[test]
+fn removed_bindings_release_their_actions() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::walk, Key::W);
+    map.add_button(ButtonId::walk, Key::Up);
+    map.add_button(ButtonId::sprint, Key::LeftShift);
+    map.add_axis(AxisId::strafe, GamepadAxis::LeftX);
+    map.add_vector_axes(VectorId::movement, GamepadAxis::LeftX, GamepadAxis::LeftY, false);
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    map.apply(&key(KeyDesc::new(Key::Up), ButtonChange::Pressed), &mut input);
+    map.apply(&key(KeyDesc::new(Key::LeftShift), ButtonChange::Pressed), &mut input);
+    map.apply(&stick(GamepadAxis::LeftX, 0.5), &mut input);
+    assert_eq!(input.axis.strafe.value, 0.5);
+    assert_eq!(input.vector.movement.value, (0.5, 0.0));
+
+    map.remove_button(ButtonId::walk, Key::W);
+    map.refresh(&mut input);
+    assert!(input.button.walk.held);
+    map.remove_button(ButtonId::walk, Key::Up);
+    map.replace_buttons(ButtonId::sprint, vec![Key::RightShift]);
+    map.clear_axis(AxisId::strafe);
+    map.clear_vector(VectorId::movement);
+    map.refresh(&mut input);
+    assert!(!input.button.walk.held);
+    assert!(!input.button.sprint.held);
+    assert_eq!(input.axis.strafe.value, 0.0);
+    assert_eq!(input.vector.movement.value, (0.0, 0.0));
+
+    map.add_button(ButtonId::walk, Key::W);
+    map.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    map.clear();
+    map.apply(&key(KeyDesc::new(Key::A), ButtonChange::Pressed), &mut input);
+    assert!(!input.button.walk.held);
+}
+
+#[test]
+fn disabled_groups_reset_their_axes() {
+    let mut map = InputMap::new();
+    map.add_axis(AxisId::strafe, GamepadAxis::LeftX);
+    map.set_group(ActionId::Axis(AxisId::strafe), "gameplay");
+    let mut input = Input::new();
+
+    map.apply(&stick(GamepadAxis::LeftX, 0.5), &mut input);
+    map.set_group_enabled("gameplay", false);
+    map.refresh(&mut input);
+    assert_eq!(input.axis.strafe.value, 0.0);
+
+    map.set_group_enabled("gameplay", true);
+    map.refresh(&mut input);
+    assert_eq!(input.axis.strafe.value, 0.5);
+}
+
+#[test]
+fn popped_contexts_release_their_actions() {
+    let mut gameplay = InputMap::new();
+    gameplay.add_button(ButtonId::walk, Key::W);
+    let mut menu = InputMap::new();
+    menu.add_button(ButtonId::sprint, Key::LeftShift);
+    menu.add_axis(AxisId::strafe, GamepadAxis::LeftX);
+    let mut stack = ContextStack::new();
+    stack.push("gameplay", gameplay, Consume::Bound);
+    stack.push("menu", menu, Consume::Bound);
+    let mut input = Input::new();
+
+    stack.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    stack.apply(&key(KeyDesc::new(Key::LeftShift), ButtonChange::Pressed), &mut input);
+    stack.apply(&stick(GamepadAxis::LeftX, 0.5), &mut input);
+    assert!(input.button.sprint.held);
+
+    stack.pop();
+    stack.refresh(&mut input);
+    assert!(input.button.walk.held);
+    assert!(!input.button.sprint.held);
+    assert_eq!(input.axis.strafe.value, 0.0);
+}
+
+#[test]
+fn disabled_contexts_keep_their_axes_at_rest() {
+    let mut gameplay = InputMap::new();
+    gameplay.add_axis(AxisId::strafe, GamepadAxis::LeftX);
+    gameplay.add_vector_axes(VectorId::movement, GamepadAxis::LeftX, GamepadAxis::LeftY, false);
+    let mut stack = ContextStack::new();
+    stack.push("gameplay", gameplay, Consume::Bound);
+    let mut input = Input::new();
+
+    stack.apply(&stick(GamepadAxis::LeftX, 0.5), &mut input);
+    stack.set_enabled("gameplay", false);
+    stack.refresh(&mut input);
+    assert_eq!(input.axis.strafe.value, 0.0);
+    assert_eq!(input.vector.movement.value, (0.0, 0.0));
+
+    stack.apply(&stick(GamepadAxis::LeftX, -0.5), &mut input);
+    assert_eq!(input.axis.strafe.value, 0.0);
+    stack.apply(&stick(GamepadAxis::LeftX, 0.0), &mut input);
+    stack.set_enabled("gameplay", true);
+    stack.apply(&stick(GamepadAxis::LeftY, 0.25), &mut input);
+    assert_eq!(input.axis.strafe.value, 0.0);
+    assert_eq!(input.vector.movement.value, (0.0, 0.25));
+}
+
+#[test]
+fn disabled_contexts_only_see_releases() {
+    let mut gameplay = InputMap::new();
+    gameplay.add_button(ButtonId::walk, Key::W);
+    let mut stack = ContextStack::new();
+    stack.push("gameplay", gameplay, Consume::All);
+    stack.push("hud", InputMap::new(), Consume::Nothing);
+    let mut input = Input::new();
+
+    stack.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    stack.set_enabled("gameplay", false);
+    assert!(!stack.is_enabled("gameplay"));
+    stack.apply(&key(KeyDesc::new(Key::W), ButtonChange::Released), &mut input);
+    assert!(!input.button.walk.held);
+    input.advance_frame();
+    stack.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    assert!(!input.button.walk.pressed);
+}
+
+#[test]
+fn buttons_stay_held_while_any_source_is_held() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::walk, Key::Up);
+    map.add_button(ButtonId::walk, KeyDesc::new(Key::W).scancode());
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::Up), ButtonChange::Pressed), &mut input);
+    map.apply(&key(KeyDesc::new(Key::W).scancode(), ButtonChange::Pressed), &mut input);
+    assert_eq!(input.button.walk.press_count, 1);
+
+    input.advance_frame();
+    map.apply(&key(KeyDesc::new(Key::Up), ButtonChange::Released), &mut input);
+    assert!(input.button.walk.held);
+    assert!(!input.button.walk.released);
+
+    input.advance_frame();
+    map.apply(&key(KeyDesc::new(Key::W).scancode(), ButtonChange::Released), &mut input);
+    assert!(!input.button.walk.held);
+    assert!(input.button.walk.released);
+    assert_eq!(input.button.walk.release_count, 1);
+}
+
+#[test]
+fn buttons_held_through_several_contexts_stay_held() {
+    let mut gameplay = InputMap::new();
+    gameplay.add_button(ButtonId::walk, Key::Up);
+    let mut hud = InputMap::new();
+    hud.add_button(ButtonId::walk, KeyDesc::new(Key::W).scancode());
+    let mut stack = ContextStack::new();
+    stack.push("gameplay", gameplay, Consume::Nothing);
+    stack.push("hud", hud, Consume::Nothing);
+    let mut input = Input::new();
+
+    stack.apply(&key(KeyDesc::new(Key::Up), ButtonChange::Pressed), &mut input);
+    input.advance_frame();
+    stack.apply(&key(KeyDesc::new(Key::W).scancode(), ButtonChange::Pressed), &mut input);
+    assert!(input.button.walk.held);
+    assert!(!input.button.walk.pressed);
+
+    stack.apply(&key(KeyDesc::new(Key::Up), ButtonChange::Released), &mut input);
+    assert!(input.button.walk.held);
+    assert!(!input.button.walk.released);
+    stack.apply(&key(KeyDesc::new(Key::W).scancode(), ButtonChange::Released), &mut input);
+    assert!(!input.button.walk.held);
+    assert!(input.button.walk.released);
+    assert_eq!(input.button.walk.release_count, 1);
+
+    stack.apply(&key(KeyDesc::new(Key::Up), ButtonChange::Pressed), &mut input);
+    stack.apply(&key(KeyDesc::new(Key::W).scancode(), ButtonChange::Pressed), &mut input);
+    stack.get_mut("hud").unwrap().clear_button(ButtonId::walk);
+    stack.refresh(&mut input);
+    assert!(input.button.walk.held);
+}
+
+#[test]
+fn buffered_presses_expire_after_their_window() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::walk, Key::W);
+    map.add_button(ButtonId::sprint, Key::LeftShift);
+    map.set_buffer(ButtonId::walk, 2);
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    map.apply(&key(KeyDesc::new(Key::LeftShift), ButtonChange::Pressed), &mut input);
+    assert!(input.button.walk.buffered());
+    assert!(!input.button.sprint.buffered());
+    for _ in 0..2 {
+        input.advance_frame();
+        assert!(input.button.walk.buffered());
+    }
+    input.advance_frame();
+    assert!(!input.button.walk.buffered());
+    assert!(!input.button.sprint.buffered());
+}
+
+#[test]
+fn consumed_presses_are_no_longer_buffered() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::walk, Key::W);
+    map.set_buffer(ButtonId::walk, 5);
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    input.advance_frame();
+    assert!(input.button.walk.consume());
+    assert!(!input.button.walk.buffered());
+    assert!(!input.button.walk.consume());
+    input.advance_frame();
+    assert!(!input.button.walk.buffered());
+}
+
+#[test]
+fn hold_lengths_follow_the_frame_clock() {
+    use std::time::Duration;
+    let mut map = InputMap::new();
+    map.set_frame_time(Some(Duration::from_millis(10)));
+    map.add_button(ButtonId::walk, Key::W);
+    let mut input = Input::new();
+
+    map.apply(&key(KeyDesc::new(Key::W), ButtonChange::Pressed), &mut input);
+    assert_eq!(input.button.walk.held_frames, 0);
+    assert_eq!(input.button.walk.held_since, Some(map.now()));
+    for frames in 1..4 {
+        map.advance_frame();
+        input.advance_frame();
+        assert_eq!(input.button.walk.held_frames, frames);
+        assert_eq!(input.button.walk.held_time(map.now()),
+                   Some(Duration::from_millis(10 * frames as u64)));
+    }
+
+    map.apply(&key(KeyDesc::new(Key::W), ButtonChange::Released), &mut input);
+    assert!(input.button.walk.released);
+    assert_eq!(input.button.walk.released_held_frames, 3);
+    assert_eq!(input.button.walk.released_held_time, Some(Duration::from_millis(30)));
+    assert_eq!(input.button.walk.held_since, None);
+    assert_eq!(input.button.walk.held_time(map.now()), None);
+}
+
+#[test]
+fn chords_press_once_every_key_is_held() {
+    let mut map = InputMap::new();
+    let chord = ButtonUpdateSource::Chord(vec![KeyDesc::new(Key::Q), KeyDesc::new(Key::E)]);
+    map.add_button(ButtonId::zoom, chord.clone());
+    assert_eq!(map.button_sources(ButtonId::zoom), vec![chord]);
+    let mut input = Input::new();
+
+    for &released in &[Key::Q, Key::E] {
+        map.apply(&key(KeyDesc::new(Key::Q), ButtonChange::Pressed), &mut input);
+        assert!(!input.button.zoom.pressed);
+        map.apply(&key(KeyDesc::new(Key::E), ButtonChange::Pressed), &mut input);
+        assert!(input.button.zoom.pressed);
+        assert!(input.button.zoom.held);
+
+        map.apply(&key(KeyDesc::new(released), ButtonChange::Released), &mut input);
+        assert!(!input.button.zoom.held);
+        assert!(input.button.zoom.released);
+        let other = if released == Key::Q { Key::E } else { Key::Q };
+        map.apply(&key(KeyDesc::new(other), ButtonChange::Released), &mut input);
+        assert_eq!(input.button.zoom.release_count, 1);
+        input.advance_frame();
+    }
+}
+
+#[test]
+fn escape_cancels_captures() {
+    let mut map: InputMap<ButtonId, SignalId, AxisId, VectorId> = InputMap::new();
+    let mut input = Input::new();
+
+    map.capture(Capture::button());
+    map.apply(&KeyEvent(Key::Escape, ButtonChange::Pressed), &mut input);
+    assert!(!map.is_capturing());
+    assert_eq!(map.take_captured(), Some(Captured::Cancelled));
+}
+
+#[test]
+fn bare_modifiers_are_only_captured_when_allowed() {
+    let mut map: InputMap<ButtonId, SignalId, AxisId, VectorId> = InputMap::new();
+    let mut input = Input::new();
+
+    map.capture(Capture::button());
+    map.apply(&key(KeyDesc::new(Key::LeftCtrl).mods(Mods::LEFT_CTRL), ButtonChange::Pressed),
+              &mut input);
+    assert!(map.is_capturing());
+    map.apply(&key(KeyDesc::new(Key::S).mods(Mods::LEFT_CTRL), ButtonChange::Pressed),
+              &mut input);
+    let ctrl_s = KeyDesc::new(Key::S).mods(Mods::CTRL);
+    assert_eq!(map.take_captured(), Some(Captured::Button(ctrl_s.into())));
+
+    map.capture(Capture::button().allow_modifiers());
+    map.apply(&key(KeyDesc::new(Key::LeftCtrl).mods(Mods::LEFT_CTRL), ButtonChange::Pressed),
+              &mut input);
+    assert_eq!(map.take_captured(), Some(Captured::Button(Key::LeftCtrl.into())));
+}
+
+#[test]
+fn captures_ignore_other_devices_and_key_types() {
+    let mut map: InputMap<ButtonId, SignalId, AxisId, VectorId> = InputMap::new();
+    let mut input = Input::new();
+
+    map.capture(Capture::button().devices(&[CaptureDevice::Gamepad]));
+    map.apply(&key(KeyDesc::new(Key::Q), ButtonChange::Pressed), &mut input);
+    map.apply(&InputChange::MouseButton(MouseButton::Left, ButtonChange::Pressed), &mut input);
+    assert!(map.is_capturing());
+    map.apply(&InputChange::GamepadButton(1, GamepadButton::South, ButtonChange::Pressed),
+              &mut input);
+    assert_eq!(map.take_captured(),
+               Some(Captured::Button(ButtonUpdateSource::GamepadButton(1, GamepadButton::South))));
+
+    map.capture(Capture::button().keytype(Keytype::Scancode));
+    map.apply(&key(KeyDesc::new(Key::Q), ButtonChange::Pressed), &mut input);
+    assert!(map.is_capturing());
+    map.apply(&key(KeyDesc::new(Key::Q).scancode(), ButtonChange::Pressed), &mut input);
+    assert_eq!(map.take_captured(),
+               Some(Captured::Button(KeyDesc::new(Key::Q).scancode().into())));
+}
+
+#[test]
+fn captured_presses_trigger_no_bindings() {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::walk, Key::W);
+    map.add_button(ButtonId::sprint, KeyDesc::new(Key::W).scancode());
+    let mut input = Input::new();
+
+    map.capture(Capture::button());
+    map.apply(&KeyEvent(Key::W, ButtonChange::Pressed), &mut input);
+    assert_eq!(map.take_captured(), Some(Captured::Button(Key::W.into())));
+    assert!(!input.button.walk.pressed);
+    assert!(!input.button.sprint.pressed);
+
+    map.apply(&KeyEvent(Key::W, ButtonChange::Released), &mut input);
+    map.apply(&KeyEvent(Key::W, ButtonChange::Pressed), &mut input);
+    assert!(input.button.walk.pressed);
+    assert!(input.button.sprint.pressed);
+}
+
+#[test]
+fn both_held_policies_pick_a_direction() {
+    // The axis value with A then D held, and after releasing each of them.
+    let cases = [(BothHeld::Cancel, 0.0), (BothHeld::LastWins, 1.0), (BothHeld::FirstWins, -1.0)];
+    for &(policy, both) in &cases {
+        for &(released, rest) in &[(Key::A, 1.0), (Key::D, -1.0)] {
+            let mut map = InputMap::new();
+            map.add_virtual_axis(AxisId::strafe, Key::A, Key::D, policy);
+            let mut input = Input::new();
+
+            map.apply(&key(KeyDesc::new(Key::A), ButtonChange::Pressed), &mut input);
+            assert_eq!(input.axis.strafe.value, -1.0);
+            map.apply(&key(KeyDesc::new(Key::D), ButtonChange::Pressed), &mut input);
+            assert_eq!(input.axis.strafe.value, both);
+            map.apply(&key(KeyDesc::new(released), ButtonChange::Released), &mut input);
+            assert_eq!(input.axis.strafe.value, rest);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+#[macro_use]
+extern crate dalgi;
+use std::time::Duration;
+use dalgi::input::*;
+
+input! {
+    pub struct Input {
+        [button]
+        pub struct Buttons<ButtonId> {
+            jump,
+            fire,
+        }
+
+        [signal]
+        pub struct Signals<SignalId> {
+            quit,
+        }
+
+        [axis]
+        pub struct Axes<AxisId> {
+            throttle,
+        }
+    }
+}
+
+fn key(key: Key, change: ButtonChange) -> InputChange {
+    InputChange::Key(KeyDesc::new(key), change)
+}
+
+fn map() -> InputMap<ButtonId, SignalId, AxisId> {
+    let mut map = InputMap::new();
+    map.add_button(ButtonId::jump, Key::Space);
+    map.add_button(ButtonId::jump, KeyDesc::new(Key::Space).scancode());
+    map.add_button(ButtonId::fire, MouseButton::Left);
+    map.add_signal(SignalId::quit, Signal::QuitRequest);
+    map.add_axis(AxisId::throttle, GamepadAxis::RightY);
+    map.set_double_tap(ButtonId::jump, Window::Frames(10));
+    map.set_frame_time(Some(Duration::from_millis(16)));
+    map
+}
+
+/// A press of space, reported both as a key code and as a scan code.
+fn space(change: ButtonChange) -> Vec<InputChange> {
+    vec![key(Key::Space, change), InputChange::Key(KeyDesc::new(Key::Space).scancode(), change)]
+}
+
+fn record() -> (Recording, Vec<Input>) {
+    let mut map = map();
+    let mut input = Input::new();
+    let mut recording = Recording::new();
+    let mut states = Vec::new();
+    let frames: Vec<Vec<Vec<InputChange>>> =
+        vec![vec![space(ButtonChange::Pressed)],
+             vec![space(ButtonChange::Released), vec![InputChange::GamepadAxis(0, GamepadAxis::RightY, 0.5)]],
+             vec![],
+             vec![space(ButtonChange::Pressed), vec![InputChange::MouseButton(MouseButton::Left, ButtonChange::Pressed)]],
+             vec![vec![InputChange::Text(TextChange::Commit("é".to_string())), Signal::QuitRequest.into()]]];
+    for events in frames {
+        for event in events {
+            recording.record(&event);
+            map.apply(&event, &mut input);
+        }
+        states.push(input.clone());
+        recording.advance_frame();
+        map.advance_frame();
+        input.advance_frame();
+    }
+    (recording, states)
+}
+
+#[test]
+fn recordings_round_trip() {
+    let (recording, _) = record();
+    let mut bytes = Vec::new();
+    recording.write_to(&mut bytes).unwrap();
+    let read = Recording::read_from(&mut &bytes[..]).unwrap();
+    assert_eq!(read, recording);
+    assert_eq!(read.frame(), 5);
+    assert_eq!(read.events().len(), 6);
+
+    bytes[4] += 1;
+    assert!(Recording::read_from(&mut &bytes[..]).is_err());
+    assert!(Recording::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn recordings_list_each_name_once() {
+    let other = key(Key::Other(1073742106), ButtonChange::Pressed);
+    let mut recording = Recording::new();
+    recording.record(&other);
+    let mut bytes = Vec::new();
+    recording.write_to(&mut bytes).unwrap();
+    let once = bytes.len();
+
+    for _ in 0..9 {
+        recording.record(&other);
+    }
+    bytes.clear();
+    recording.write_to(&mut bytes).unwrap();
+    // A frame delta and a change count, then the change: its kind, the
+    // position of the key name, the key type, the modifiers and the press.
+    assert_eq!(bytes.len(), once + 9 * 8);
+    let read = Recording::read_from(&mut &bytes[..]).unwrap();
+    assert_eq!(read.events()[9].1, vec![other]);
+}
+
+#[test]
+fn replays_reproduce_the_recorded_states() {
+    let (recording, states) = record();
+    let mut map = map();
+    let mut input = Input::new();
+    let mut replay = Replay::new(recording);
+    for state in &states {
+        replay.apply(&mut map, &mut input);
+        assert_eq!(input, *state);
+        replay.advance_frame();
+        map.advance_frame();
+        input.advance_frame();
+    }
+    assert!(replay.is_finished());
+    assert!(states[3].button.jump.double_pressed);
+}
+
+#[test]
+fn replays_go_through_context_stacks() {
+    let (recording, states) = record();
+    let mut stack = ContextStack::new();
+    stack.push("gameplay", map(), Consume::Bound);
+    let mut input = Input::new();
+    let mut replay = Replay::new(recording);
+    for state in &states {
+        replay.apply(&mut stack, &mut input);
+        assert_eq!(input, *state);
+        replay.advance_frame();
+        stack.advance_frame();
+        input.advance_frame();
+    }
+}
+
+#[test]
+fn state_recordings_round_trip() {
+    let (_, states) = record();
+    let mut recording = StateRecording::new();
+    for state in &states {
+        recording.record(&mut state.clone());
+    }
+    let mut bytes = Vec::new();
+    recording.write_to(&mut bytes).unwrap();
+    let read = StateRecording::<Input>::read_from(&mut &bytes[..]).unwrap();
+    assert_eq!(read, recording);
+
+    let mut input = Input::new();
+    for (frame, state) in states.iter().enumerate() {
+        assert!(read.restore(frame, &mut input));
+        assert_eq!(input.button.jump.held, state.button.jump.held);
+        assert_eq!(input.button.jump.double_pressed, state.button.jump.double_pressed);
+        assert_eq!(input.signal, state.signal);
+        assert_eq!(input.axis.throttle.value, state.axis.throttle.value);
+    }
+    assert!(!read.restore(states.len(), &mut input));
+}
+
+#[test]
+fn state_recordings_with_too_many_frames_are_rejected() {
+    let mut recording = StateRecording::new();
+    recording.record(&mut Input::new());
+    let mut bytes = Vec::new();
+    recording.write_to(&mut bytes).unwrap();
+    // The frame count comes just before the length of the frame data.
+    let mut empty = Vec::new();
+    StateRecording::<Input>::new().write_to(&mut empty).unwrap();
+    let count = empty.len() - 2;
+    assert_eq!(bytes[count], 1);
+    bytes[count] = 0x7f;
+    assert!(StateRecording::<Input>::read_from(&mut &bytes[..]).is_err());
+}
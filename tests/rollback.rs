@@ -0,0 +1,85 @@
+#[macro_use]
+extern crate dalgi;
+use dalgi::input::*;
+
+input! {
+    pub struct Input {
+        [button]
+        pub struct Buttons<ButtonId> {
+            jump,
+        }
+
+        [signal]
+        pub struct Signals<SignalId> {
+            quit,
+        }
+    }
+}
+
+fn jumping() -> Input {
+    let mut input = Input::new();
+    input.button.jump.press(None);
+    input
+}
+
+#[test]
+fn missing_frames_repeat_the_last_input() {
+    let mut buffer = RollbackBuffer::new();
+    buffer.confirm(0, jumping());
+    buffer.advance_frame();
+    buffer.advance_frame();
+
+    let predicted = buffer.current().clone();
+    assert!(predicted.button.jump.held);
+    assert!(!predicted.button.jump.pressed);
+    assert!(!buffer.is_confirmed(2));
+    assert_eq!(buffer.confirmed_until(), Some(0));
+}
+
+#[test]
+fn read_mispredictions_are_reported() {
+    let mut buffer: RollbackBuffer<Input> = RollbackBuffer::default();
+    buffer.confirm(0, Input::new());
+    buffer.advance_frame();
+    buffer.advance_frame();
+    assert!(!buffer.current().button.jump.held);
+
+    buffer.confirm(1, Input::new());
+    assert_eq!(buffer.take_misprediction(), None);
+    buffer.confirm(2, jumping());
+    assert_eq!(buffer.take_misprediction(), Some(2));
+    assert!(buffer.current().button.jump.pressed);
+    assert_eq!(buffer.confirmed_until(), Some(2));
+
+    // Unread frames can't be mispredicted.
+    buffer.confirm(4, Input::new());
+    assert_eq!(buffer.take_misprediction(), None);
+}
+
+#[test]
+fn local_input_is_delayed() {
+    let mut buffer = RollbackBuffer::with_delay(2);
+    assert_eq!(buffer.push_local(&jumping()), 2);
+    assert!(!buffer.current().button.jump.held);
+    buffer.advance_frame();
+    buffer.advance_frame();
+    assert!(buffer.current().button.jump.pressed);
+
+    buffer.discard_before(2);
+    assert!(buffer.state(1).is_none());
+    assert!(buffer.state(2).is_some());
+}
+
+#[test]
+fn input_too_far_ahead_is_ignored() {
+    let mut buffer = RollbackBuffer::new();
+    buffer.set_max_ahead(8);
+    assert_eq!(buffer.max_ahead(), 8);
+    buffer.confirm(u64::MAX - 1, jumping());
+    buffer.confirm(9, jumping());
+    assert!(!buffer.is_confirmed(9));
+    assert!(buffer.state(9).is_none());
+
+    buffer.confirm(8, jumping());
+    assert!(buffer.is_confirmed(8));
+}